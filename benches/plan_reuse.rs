@@ -0,0 +1,48 @@
+// 短い信号を繰り返し解析するときの、FFTのプランを再利用する場合と毎回作る場合の時間を比べる
+//
+// cargo bench --bench plan_reuse で実行する。
+use realfft::RealFftPlanner;
+use std::time::{Duration, Instant};
+
+// 解析する信号の数と長さ
+const CLIPS: usize = 1000;
+const CLIP_LEN: usize = 2048;
+
+// 毎回プランナーを作ってスペクトルを計算する (プランを再利用しない場合)
+fn spectrum_with_new_planner(samples: &[f64]) -> Vec<f64> {
+    let len = signal::fft_len(samples.len());
+    let fft = RealFftPlanner::<f64>::new().plan_fft_forward(len);
+
+    let mut input = samples.to_vec();
+    input.resize(len, 0.0);
+    let mut result = fft.make_output_vec();
+    fft.process(&mut input, &mut result).unwrap();
+
+    result.iter().take(len / 2).map(|x| x.norm()).collect()
+}
+
+// 全ての信号のスペクトルを計算するのにかかった時間
+fn measure(clips: &[Vec<f64>], spectrum: impl Fn(&[f64]) -> Vec<f64>) -> Duration {
+    let start = Instant::now();
+    for clip in clips {
+        std::hint::black_box(spectrum(std::hint::black_box(clip)));
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let clips: Vec<Vec<f64>> = (0..CLIPS)
+        .map(|i| {
+            let freq = 110.0 + i as f64;
+            (0..CLIP_LEN).map(|n| (2.0 * std::f64::consts::PI * freq * n as f64 / 44100.0).sin()).collect()
+        })
+        .collect();
+
+    let reused = measure(&clips, signal::spectrum);
+    let planned = measure(&clips, spectrum_with_new_planner);
+
+    println!("{}個の{}サンプルの信号", CLIPS, CLIP_LEN);
+    println!("プランを再利用: {:?}", reused);
+    println!("毎回プランを作成: {:?}", planned);
+    println!("{:.1}倍", planned.as_secs_f64() / reused.as_secs_f64());
+}
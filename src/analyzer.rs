@@ -0,0 +1,132 @@
+use crate::{AnalyzeOptions, ChordResult, SILENCE_THRESHOLD, analyze_spectrum, apply_window, fft_len, plan_fft, spectrum_with};
+use realfft::RealToComplex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+// 少しずつ届くサンプルを溜めながらコードを解析する
+//
+// 直近のframe_sizeサンプルだけをリングバッファに保持し、FFTのプランは最初に1度だけ作る。
+pub struct Analyzer {
+    sample_rate: u32,
+    frame_size: usize,
+    options: AnalyzeOptions,
+    buffer: VecDeque<f64>,
+    fft: Arc<dyn RealToComplex<f64>>,
+}
+
+impl Analyzer {
+    // frame_sizeサンプルごとに解析するAnalyzerを作る
+    pub fn new(sample_rate: u32, frame_size: usize, options: AnalyzeOptions) -> Self {
+        let frame_size = frame_size.max(1);
+        let fft = plan_fft(fft_len(frame_size));
+
+        Analyzer {
+            sample_rate,
+            frame_size,
+            options,
+            buffer: VecDeque::with_capacity(frame_size),
+            fft,
+        }
+    }
+
+    // サンプルを追加する (frame_sizeを超えた古いサンプルは捨てる)
+    pub fn push_samples(&mut self, samples: &[f64]) {
+        self.buffer.extend(samples);
+
+        let overflow = self.buffer.len().saturating_sub(self.frame_size);
+        self.buffer.drain(..overflow);
+    }
+
+    // 溜まっているサンプルが解析に足りるかどうか
+    pub fn is_ready(&self) -> bool {
+        self.buffer.len() >= self.frame_size
+    }
+
+    // 溜まっているサンプルを捨てる
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    // 直近のframe_sizeサンプルのコードを返す
+    //
+    // サンプルが足りない場合や無音の場合はNoneを返す。
+    pub fn current_chord(&self) -> Option<ChordResult> {
+        if !self.is_ready() {
+            return None;
+        }
+
+        let frame: Vec<f64> = self.buffer.iter().copied().collect();
+        if frame.iter().all(|x| x.abs() < SILENCE_THRESHOLD) {
+            return None;
+        }
+
+        let frame = apply_window(&frame, self.options.window);
+        let output = spectrum_with(&frame, &*self.fft);
+        analyze_spectrum(output, self.sample_rate, self.fft.len(), &self.options).ok().map(|detail| detail.chord)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::sine;
+
+    const SAMPLE_RATE: u32 = 44100;
+    const FRAME_SIZE: usize = 16384;
+
+    #[test]
+    fn not_ready_until_a_full_frame() {
+        let mut analyzer = Analyzer::new(SAMPLE_RATE, FRAME_SIZE, AnalyzeOptions::default());
+        assert!(!analyzer.is_ready());
+        assert_eq!(analyzer.current_chord(), None);
+
+        let samples = sine(&[261.63, 329.63, 392.0], SAMPLE_RATE, FRAME_SIZE);
+        analyzer.push_samples(&samples[..FRAME_SIZE - 1]);
+        assert!(!analyzer.is_ready());
+        assert_eq!(analyzer.current_chord(), None);
+
+        analyzer.push_samples(&samples[FRAME_SIZE - 1..]);
+        assert!(analyzer.is_ready());
+    }
+
+    #[test]
+    fn overflow_drops_oldest_samples() {
+        let mut analyzer = Analyzer::new(SAMPLE_RATE, 4, AnalyzeOptions::default());
+        analyzer.push_samples(&[1.0, 2.0, 3.0]);
+        analyzer.push_samples(&[4.0, 5.0, 6.0]);
+
+        assert_eq!(analyzer.buffer, [3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn clear_empties_the_buffer() {
+        let mut analyzer = Analyzer::new(SAMPLE_RATE, 4, AnalyzeOptions::default());
+        analyzer.push_samples(&[1.0, 2.0, 3.0, 4.0]);
+        assert!(analyzer.is_ready());
+
+        analyzer.clear();
+        assert!(!analyzer.is_ready());
+        assert!(analyzer.buffer.is_empty());
+    }
+
+    #[test]
+    fn silent_frame_has_no_chord() {
+        let mut analyzer = Analyzer::new(SAMPLE_RATE, FRAME_SIZE, AnalyzeOptions::default());
+        analyzer.push_samples(&vec![0.0; FRAME_SIZE]);
+
+        assert!(analyzer.is_ready());
+        assert_eq!(analyzer.current_chord(), None);
+    }
+
+    #[test]
+    fn c_major_frame_is_detected() {
+        let mut analyzer = Analyzer::new(SAMPLE_RATE, FRAME_SIZE, AnalyzeOptions::default());
+        // 少しずつ届いても、溜まった直近のフレームで解析する
+        for chunk in sine(&[261.63, 329.63, 392.0], SAMPLE_RATE, 2 * FRAME_SIZE).chunks(1000) {
+            analyzer.push_samples(chunk);
+        }
+
+        let chord = analyzer.current_chord().unwrap();
+        assert_eq!(chord.to_string(), "C4 major");
+    }
+}
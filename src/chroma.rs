@@ -0,0 +1,153 @@
+use crate::prelude::*;
+use crate::{AnalyzeError, ChordDictionary, ChordResult, NOTES, bin_to_freq};
+
+// クロマの計算に使う周波数の範囲 (A0〜C8)
+const MIN_FREQ: f64 = 27.5;
+const MAX_FREQ: f64 = 4186.0;
+
+// 振幅スペクトルのエネルギーを12個の音高クラスに畳み込む
+//
+// 0がC、11がBで、最大値が1.0になるように正規化する。
+// outputはspectrumの結果で、fft_lenはゼロ埋め後のFFTの長さ、tuning_hzは基準音A4の周波数。
+pub fn chroma(output: &[f64], sample_rate: u32, fft_len: usize, tuning_hz: f64) -> [f64; 12] {
+    let mut chroma = [0.0; 12];
+
+    for (i, x) in output.iter().enumerate() {
+        let freq = bin_to_freq(i as f64, fft_len, sample_rate);
+        if !(MIN_FREQ..=MAX_FREQ).contains(&freq) {
+            continue;
+        }
+
+        // Cからの半音数 (C4は基準音A4から9半音下)
+        let semitones = ((freq / tuning_hz).log2() * 12f64).round() as i32 + 9;
+        chroma[semitones.rem_euclid(12) as usize] += x * x;
+    }
+
+    let max = chroma.iter().fold(0.0, |m: f64, v| v.max(m));
+    if max > 0.0 {
+        chroma.iter_mut().for_each(|x| *x /= max);
+    }
+
+    chroma
+}
+
+// クロマと辞書のコードのテンプレートのコサイン類似度を取り、最も近いコードとその類似度を返す
+//
+// クロマには音の高さの情報がないため、オクターブは4とする。
+// クロマが全て0の場合はEmptySignal、有限でない値を含む場合はInvalidInput、
+// 辞書が空の場合はInvalidDictionaryを返す。
+pub fn analyze_chord_from_chroma(chroma: &[f64; 12], dictionary: &ChordDictionary) -> Result<(ChordResult, f64), AnalyzeError> {
+    let norm = chroma.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if !norm.is_finite() {
+        return Err(AnalyzeError::InvalidInput(format!("クロマに有限でない値が含まれています: {:?}", chroma)));
+    }
+    if norm == 0.0 {
+        return Err(AnalyzeError::EmptySignal);
+    }
+
+    let mut best: Option<(ChordResult, f64)> = None;
+    for (intervals, quality) in dictionary.iter() {
+        for root in 0..12 {
+            let energy: f64 = intervals.iter().map(|x| chroma[(root + *x as usize) % 12]).sum();
+            let score = energy / (norm * (intervals.len() as f64).sqrt());
+
+            // 同じ類似度の場合は構成音の少ないコード、コード名の順に優先する
+            let better = match &best {
+                Some((result, s)) => {
+                    score > *s
+                        || (score == *s && (intervals.len(), quality) < (result.intervals.len(), &result.quality))
+                },
+                None => true,
+            };
+
+            if better {
+                let result = ChordResult {
+                    root_note: NOTES[(root + 3) % 12].to_string(),
+                    octave: 4,
+                    // C4 (60) からのオクターブ内の音
+                    root_midi: 60 + root as i32,
+                    quality: quality.clone(),
+                    intervals: intervals.clone(),
+                    bass_note: None,
+                    inversion: 0,
+                    deviations: 0,
+                    peaks: Vec::new(),
+                    notes: Vec::new(),
+                    confidence: score,
+                };
+                best = Some((result, score));
+            }
+        }
+    }
+
+    best.ok_or_else(|| AnalyzeError::InvalidDictionary("コードが1つも登録されていません".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // freqのビンだけに振幅のあるスペクトル
+    fn single_bin_spectrum(freq: f64, sample_rate: u32, fft_len: usize) -> Vec<f64> {
+        let mut output = vec![0.0; fft_len / 2];
+        output[crate::freq_to_bin(freq, fft_len, sample_rate).round() as usize] = 1.0;
+        output
+    }
+
+    // 最も強い音高クラス (0がC)
+    fn strongest(chroma: &[f64; 12]) -> usize {
+        (0..12).max_by(|a, b| chroma[*a].total_cmp(&chroma[*b])).unwrap()
+    }
+
+    #[test]
+    fn chroma_follows_tuning() {
+        let (sample_rate, fft_len) = (44100, 1 << 16);
+        let output = single_bin_spectrum(415.0, sample_rate, fft_len);
+
+        // A415の基準ではAになり、A440の基準ではG#になる
+        assert_eq!(strongest(&chroma(&output, sample_rate, fft_len, 415.0)), 9);
+        assert_eq!(strongest(&chroma(&output, sample_rate, fft_len, crate::STANDARD_TUNING_HZ)), 8);
+    }
+
+    #[test]
+    fn chroma_of_triad_matches_template() {
+        let (sample_rate, fft_len) = (44100, 1 << 16);
+        let mut output = vec![0.0; fft_len / 2];
+        for freq in [261.63, 329.63, 392.0] {
+            output[crate::freq_to_bin(freq, fft_len, sample_rate).round() as usize] = 1.0;
+        }
+
+        let chroma = chroma(&output, sample_rate, fft_len, crate::STANDARD_TUNING_HZ);
+        let (result, score) = analyze_chord_from_chroma(&chroma, &ChordDictionary::default()).unwrap();
+        assert_eq!((result.root_note.as_str(), result.quality.as_str()), ("C", "major"));
+        assert!((score - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn zero_chroma_is_no_chord() {
+        let dictionary = ChordDictionary::default();
+
+        assert!(matches!(analyze_chord_from_chroma(&[0.0; 12], &dictionary), Err(AnalyzeError::EmptySignal)));
+        assert!(matches!(analyze_chord_from_chroma(&chroma(&[], 44100, 0, crate::STANDARD_TUNING_HZ), &dictionary), Err(AnalyzeError::EmptySignal)));
+
+        let mut invalid = [0.0; 12];
+        invalid[0] = f64::NAN;
+        assert!(matches!(analyze_chord_from_chroma(&invalid, &dictionary), Err(AnalyzeError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn chroma_matching_uses_given_dictionary() {
+        // C E Gのクロマ
+        let mut chroma = [0.0; 12];
+        for i in [0, 4, 7] {
+            chroma[i] = 1.0;
+        }
+
+        let mut dictionary = ChordDictionary::empty();
+        dictionary.insert(vec![0, 7], "power".to_string()).unwrap();
+        let (result, _) = analyze_chord_from_chroma(&chroma, &dictionary).unwrap();
+        assert_eq!((result.root_note.as_str(), result.quality.as_str()), ("C", "power"));
+
+        assert!(matches!(analyze_chord_from_chroma(&chroma, &ChordDictionary::empty()), Err(AnalyzeError::InvalidDictionary(_))));
+    }
+}
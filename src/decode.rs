@@ -0,0 +1,267 @@
+use crate::AnalyzeError;
+use hound::WavSpec;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+// symphoniaで音声ファイルをデコードし、インターリーブされたサンプルを返す
+//
+// 返すWavSpecはチャンネル数とサンプリング周波数だけが意味を持ち、
+// サンプルは32bit floatとして扱う。
+pub(crate) fn decode_file(path: &str) -> Result<(WavSpec, Vec<f64>), AnalyzeError> {
+    let file = std::fs::File::open(path).map_err(|e| AnalyzeError::from_symphonia(Error::IoError(e), path))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    // 拡張子をヒントにしてフォーマットを判別する
+    let mut hint = Hint::new();
+    if let Some(ext) = Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| AnalyzeError::from_symphonia(e, path))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(AnalyzeError::EmptySignal)?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut channels = track.codec_params.channels.map(|channels| channels.count() as u16);
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| AnalyzeError::from_symphonia(e, path))?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            // ファイルの終端
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(AnalyzeError::from_symphonia(e, path)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // 壊れたパケットは読み飛ばす
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(AnalyzeError::from_symphonia(e, path)),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate.get_or_insert(spec.rate);
+        channels.get_or_insert(spec.channels.count() as u16);
+
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend(buffer.samples().iter().map(|&s| s as f64));
+    }
+
+    let (Some(sample_rate), Some(channels)) = (sample_rate, channels) else {
+        return Err(AnalyzeError::EmptySignal);
+    };
+
+    let spec = WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    Ok((spec, samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempFile;
+    #[cfg(feature = "flac")]
+    use crate::test_util::sine;
+    #[cfg(feature = "flac")]
+    use crate::{WindowKind, get_audio, get_wave};
+
+    // ビット単位で書き込むバッファ
+    #[cfg(feature = "flac")]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        current: u8,
+        bits: u32,
+    }
+
+    #[cfg(feature = "flac")]
+    impl BitWriter {
+        fn new() -> Self {
+            BitWriter { bytes: Vec::new(), current: 0, bits: 0 }
+        }
+
+        // valueの下位bitsビットを上位から順に書き込む
+        fn write(&mut self, value: u64, bits: u32) {
+            for i in (0..bits).rev() {
+                self.current = (self.current << 1) | ((value >> i) & 1) as u8;
+                self.bits += 1;
+                if self.bits == 8 {
+                    self.bytes.push(self.current);
+                    self.current = 0;
+                    self.bits = 0;
+                }
+            }
+        }
+
+        // 残りを0で埋めてバイト列を返す
+        fn finish(mut self) -> Vec<u8> {
+            if self.bits > 0 {
+                self.write(0, 8 - self.bits);
+            }
+            self.bytes
+        }
+    }
+
+    // FLACのフレームヘッダとフレームの誤り検出符号
+    #[cfg(feature = "flac")]
+    fn crc(bytes: &[u8], width: u32, polynomial: u32) -> u32 {
+        let mask = (1u32 << width) - 1;
+        let mut crc = 0u32;
+        for &byte in bytes {
+            crc ^= (byte as u32) << (width - 8);
+            for _ in 0..8 {
+                crc = if crc & (1 << (width - 1)) != 0 { (crc << 1) ^ polynomial } else { crc << 1 } & mask;
+            }
+        }
+        crc
+    }
+
+    // 16bitのインターリーブされたサンプルを、圧縮しないVERBATIMサブフレームだけのFLACにする
+    #[cfg(feature = "flac")]
+    fn encode_flac(samples: &[i16], channels: u16, sample_rate: u32) -> Vec<u8> {
+        const BLOCK_SIZE: usize = 4096;
+        let frames = samples.len() / channels as usize;
+
+        let mut info = BitWriter::new();
+        info.write(BLOCK_SIZE as u64, 16);
+        info.write(BLOCK_SIZE as u64, 16);
+        info.write(0, 24);
+        info.write(0, 24);
+        info.write(sample_rate as u64, 20);
+        info.write(channels as u64 - 1, 3);
+        info.write(15, 5);
+        info.write(frames as u64, 36);
+        info.write(0, 64);
+        info.write(0, 64);
+
+        // 最後のメタデータブロックであるSTREAMINFO (34バイト)
+        let mut flac = b"fLaC".to_vec();
+        flac.extend([0x80, 0, 0, 34]);
+        flac.extend(info.finish());
+
+        for (number, start) in (0..frames).step_by(BLOCK_SIZE).enumerate() {
+            let count = BLOCK_SIZE.min(frames - start);
+
+            let mut header = BitWriter::new();
+            header.write(0x3FFE, 14);
+            header.write(0, 2);
+            // ブロックサイズはヘッダの末尾に16bitで書き、サンプリング周波数はSTREAMINFOのものを使う
+            header.write(7, 4);
+            header.write(0, 4);
+            header.write(channels as u64 - 1, 4);
+            header.write(4, 3);
+            header.write(0, 1);
+            header.write(number as u64, 8);
+            header.write(count as u64 - 1, 16);
+            let mut frame = header.finish();
+            frame.push(crc(&frame, 8, 0x07) as u8);
+
+            let mut body = BitWriter::new();
+            for channel in 0..channels as usize {
+                body.write(0b0000_0010, 8);
+                for i in start..start + count {
+                    body.write(samples[i * channels as usize + channel] as u16 as u64, 16);
+                }
+            }
+            frame.extend(body.finish());
+            let footer = crc(&frame, 16, 0x8005) as u16;
+            frame.extend(footer.to_be_bytes());
+
+            flac.extend(frame);
+        }
+
+        flac
+    }
+
+    #[cfg(feature = "flac")]
+    #[test]
+    fn flac_decodes_to_same_samples_as_wave() {
+        let left = sine(&[261.63, 329.63], 44100, 5000);
+        let right = sine(&[392.0], 44100, 5000);
+        let samples: Vec<i16> = left.iter().zip(&right)
+            .flat_map(|(l, r)| [(l * 20000.0) as i16, (r * 20000.0) as i16])
+            .collect();
+
+        let wave = TempFile::new("equivalent.wav");
+        let spec = WavSpec { channels: 2, sample_rate: 44100, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(wave.path(), spec).unwrap();
+        for &sample in &samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let flac = TempFile::new("equivalent.flac");
+        std::fs::write(flac.path(), encode_flac(&samples, 2, 44100)).unwrap();
+
+        let (wave_spec, wave_samples) = get_wave(wave.path(), WindowKind::Rectangular).unwrap();
+        let (flac_spec, flac_samples) = get_audio(flac.path(), WindowKind::Rectangular).unwrap();
+
+        assert_eq!((flac_spec.channels, flac_spec.sample_rate), (wave_spec.channels, wave_spec.sample_rate));
+        assert_eq!(flac_samples, wave_samples);
+    }
+
+    #[test]
+    fn undecodable_file_is_an_error() {
+        let file = TempFile::new("garbage.flac");
+        std::fs::write(file.path(), b"not an audio file").unwrap();
+
+        assert!(decode_file(file.path()).is_err());
+    }
+
+    #[test]
+    fn missing_file_is_reported() {
+        let file = TempFile::new("missing.flac");
+
+        assert!(matches!(decode_file(file.path()), Err(AnalyzeError::FileNotFound(_))));
+    }
+
+    // 無音のMPEG-1 Layer IIIのフレーム (128kbps、44.1kHz、モノラル) をcount個並べる
+    //
+    // サイド情報と主データが全て0のフレームは、全てのサンプルが0にデコードされる。
+    #[cfg(feature = "mp3")]
+    fn silent_mp3(count: usize) -> Vec<u8> {
+        // 144 * 128000 / 44100バイト
+        const FRAME_LEN: usize = 417;
+        let mut frame = vec![0; FRAME_LEN];
+        frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC0]);
+        frame.repeat(count)
+    }
+
+    #[cfg(feature = "mp3")]
+    #[test]
+    fn mp3_smoke_test() {
+        let file = TempFile::new("silent.mp3");
+        std::fs::write(file.path(), silent_mp3(10)).unwrap();
+
+        let (spec, samples) = decode_file(file.path()).unwrap();
+        assert_eq!((spec.channels, spec.sample_rate), (1, 44100));
+        // 1フレームは1152サンプル
+        assert!(!samples.is_empty() && samples.len() % 1152 == 0, "{}", samples.len());
+        assert!(samples.iter().all(|x| *x == 0.0));
+    }
+}
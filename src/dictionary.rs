@@ -0,0 +1,186 @@
+use crate::AnalyzeError;
+use crate::prelude::*;
+use alloc::collections::BTreeMap;
+#[cfg(feature = "json")]
+use serde::Deserialize;
+
+// ルート音からの相対音程とコード名の辞書
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChordDictionary {
+    chords: BTreeMap<Vec<i32>, String>,
+}
+
+// コード定義ファイルの形式
+//
+// {"replace": false, "chords": {"power": [0, 7]}} のように、コード名と音程を対応付ける。
+// replaceがtrueの場合は組み込みの辞書を使わない。
+#[cfg(feature = "json")]
+#[derive(Deserialize)]
+struct ChordFile {
+    #[serde(default)]
+    replace: bool,
+    chords: BTreeMap<String, Vec<i32>>,
+}
+
+impl Default for ChordDictionary {
+    // 組み込みの辞書
+    fn default() -> Self {
+        let mut chord_map: BTreeMap<Vec<i32>, String> = BTreeMap::new();
+        chord_map.insert(vec![0, 4, 7], "major".to_string());
+        chord_map.insert(vec![0, 3, 7], "minor".to_string());
+        chord_map.insert(vec![0, 4, 7, 10], "seventh".to_string());
+        chord_map.insert(vec![0, 4, 7, 11], "major_seventh".to_string());
+        chord_map.insert(vec![0, 3, 7, 10], "minor_seventh".to_string());
+        chord_map.insert(vec![0, 3, 7, 11], "minor_major_seventh".to_string());
+        chord_map.insert(vec![0, 4, 8], "augmented".to_string());
+        chord_map.insert(vec![0, 3, 6], "diminished".to_string());
+        chord_map.insert(vec![0, 3, 6, 9], "diminished_seventh".to_string());
+        chord_map.insert(vec![0, 3, 6, 10], "minor_seventh_flat_five".to_string());
+        chord_map.insert(vec![0, 2, 7], "sus2".to_string());
+        chord_map.insert(vec![0, 5, 7], "sus4".to_string());
+        chord_map.insert(vec![0, 2, 7, 10], "seventh_sus2".to_string());
+        chord_map.insert(vec![0, 5, 7, 10], "seventh_sus4".to_string());
+        chord_map.insert(vec![0, 4, 7, 9], "sixth".to_string());
+        chord_map.insert(vec![0, 3, 7, 9], "minor_sixth".to_string());
+        // 9thは1オクターブ内に畳み込んだ2半音として扱う
+        chord_map.insert(vec![0, 2, 4, 7], "add9".to_string());
+        chord_map.insert(vec![0, 2, 3, 7], "minor_add9".to_string());
+        // 9th、11th、13thのテンションコードも1オクターブ内に畳み込む (9thは2、11thは5、13thは9半音)
+        // 7thを含むことでadd9やsixthと区別する
+        chord_map.insert(vec![0, 2, 4, 7, 10], "ninth".to_string());
+        chord_map.insert(vec![0, 2, 4, 7, 11], "major_ninth".to_string());
+        chord_map.insert(vec![0, 2, 3, 7, 10], "minor_ninth".to_string());
+        chord_map.insert(vec![0, 2, 4, 5, 7, 10], "eleventh".to_string());
+        chord_map.insert(vec![0, 2, 3, 5, 7, 10], "minor_eleventh".to_string());
+        chord_map.insert(vec![0, 2, 4, 7, 9, 10], "thirteenth".to_string());
+        // ♭9thと#9thは短2度や短3度と区別するため12以上の音程で定義する
+        // (IntervalMode::MultiOctaveで1オクターブ上にある場合にだけ一致する)
+        chord_map.insert(vec![0, 4, 7, 10, 13], "seventh_flat_ninth".to_string());
+        chord_map.insert(vec![0, 4, 7, 10, 15], "seventh_sharp_ninth".to_string());
+
+        ChordDictionary { chords: chord_map }
+    }
+}
+
+impl ChordDictionary {
+    // 空の辞書
+    pub fn empty() -> Self {
+        ChordDictionary { chords: BTreeMap::new() }
+    }
+
+    // JSONのコード定義を読み込む
+    //
+    // replaceがfalseの場合は組み込みの辞書に上書きで追加する。
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str) -> Result<Self, AnalyzeError> {
+        let file: ChordFile = serde_json::from_str(json)
+            .map_err(|e| AnalyzeError::InvalidDictionary(e.to_string()))?;
+
+        let mut dictionary = if file.replace { ChordDictionary::empty() } else { ChordDictionary::default() };
+        for (name, intervals) in file.chords {
+            dictionary.insert(intervals, name)?;
+        }
+
+        Ok(dictionary)
+    }
+
+    // JSONのコード定義ファイルを読み込む
+    #[cfg(all(feature = "io", feature = "json"))]
+    pub fn load(path: &str) -> Result<Self, AnalyzeError> {
+        let json = std::fs::read_to_string(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => AnalyzeError::FileNotFound(path.to_string()),
+            _ => AnalyzeError::DecodeError(e.to_string()),
+        })?;
+
+        ChordDictionary::from_json(&json)
+    }
+
+    // コードを追加する (同じ音程のコードがある場合は置き換える)
+    //
+    // 音程は0から始まり、重複なく昇順に並んでいる必要がある。
+    pub fn insert(&mut self, intervals: Vec<i32>, name: String) -> Result<(), AnalyzeError> {
+        if intervals.first() != Some(&0) {
+            return Err(AnalyzeError::InvalidDictionary(format!("{}: 音程は0から始めてください: {:?}", name, intervals)));
+        }
+        if intervals.windows(2).any(|x| x[0] >= x[1]) {
+            return Err(AnalyzeError::InvalidDictionary(format!("{}: 音程は重複なく昇順に並べてください: {:?}", name, intervals)));
+        }
+
+        self.chords.insert(intervals, name);
+        Ok(())
+    }
+
+    // 音程に対応するコード名を返す
+    pub fn get(&self, intervals: &[i32]) -> Option<&str> {
+        self.chords.get(intervals).map(|name| name.as_str())
+    }
+
+    // 全てのコードの音程とコード名を返す
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<i32>, &String)> {
+        self.chords.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chords.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chords.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn custom_entry_overrides_builtin() {
+        let dictionary = ChordDictionary::from_json(r#"{"chords": {"triad": [0, 4, 7], "power": [0, 7]}}"#).unwrap();
+
+        assert_eq!(dictionary.get(&[0, 4, 7]), Some("triad"));
+        assert_eq!(dictionary.get(&[0, 7]), Some("power"));
+        // 上書きしていない組み込みのコードは残る
+        assert_eq!(dictionary.get(&[0, 3, 7]), Some("minor"));
+        assert_eq!(dictionary.len(), ChordDictionary::default().len() + 1);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn replace_drops_builtin_chords() {
+        let dictionary = ChordDictionary::from_json(r#"{"replace": true, "chords": {"power": [0, 7]}}"#).unwrap();
+
+        assert_eq!(dictionary.len(), 1);
+        assert_eq!(dictionary.get(&[0, 4, 7]), None);
+    }
+
+    #[test]
+    fn rejects_invalid_intervals() {
+        for intervals in [vec![4, 7], vec![], vec![0, 7, 4], vec![0, 4, 4, 7]] {
+            let mut dictionary = ChordDictionary::empty();
+            assert!(
+                matches!(dictionary.insert(intervals.clone(), "bad".to_string()), Err(AnalyzeError::InvalidDictionary(_))),
+                "{:?}",
+                intervals
+            );
+            assert!(dictionary.is_empty());
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn rejects_malformed_json() {
+        assert!(matches!(ChordDictionary::from_json("{"), Err(AnalyzeError::InvalidDictionary(_))));
+        assert!(matches!(ChordDictionary::from_json(r#"{"chords": {"major": "C E G"}}"#), Err(AnalyzeError::InvalidDictionary(_))));
+        assert!(matches!(ChordDictionary::from_json(r#"{"chords": {"bad": [4, 7]}}"#), Err(AnalyzeError::InvalidDictionary(_))));
+    }
+
+    #[test]
+    fn builtin_intervals_are_valid() {
+        let mut dictionary = ChordDictionary::empty();
+        for (intervals, name) in ChordDictionary::default().iter() {
+            dictionary.insert(intervals.clone(), name.clone()).unwrap();
+        }
+        assert_eq!(dictionary, ChordDictionary::default());
+    }
+}
@@ -0,0 +1,90 @@
+use crate::prelude::*;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+// 解析中に発生するエラー
+#[derive(Debug)]
+pub enum AnalyzeError {
+    // ファイルが見つからない
+    FileNotFound(String),
+    // 対応していない音声フォーマット
+    UnsupportedFormat(String),
+    // ファイルの読み込みやデコードに失敗した
+    DecodeError(String),
+    // 解析できる信号がない
+    EmptySignal,
+    // コードの定義が正しくない
+    InvalidDictionary(String),
+    // サンプリング周波数が解析する周波数の範囲に対して低すぎる
+    SampleRateTooLow(u32),
+    // 解析する時間の範囲が音声の長さを超えている
+    InvalidRange(String),
+    // 解析できない値が入力に含まれている (負の周波数や有限でないサンプルなど)
+    InvalidInput(String),
+}
+
+impl fmt::Display for AnalyzeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalyzeError::FileNotFound(path) => write!(f, "ファイルが見つかりません: {}", path),
+            AnalyzeError::UnsupportedFormat(format) => write!(f, "対応していないフォーマットです: {}", format),
+            AnalyzeError::DecodeError(message) => write!(f, "ファイルを読み込めません: {}", message),
+            AnalyzeError::EmptySignal => write!(f, "音声が含まれていません"),
+            AnalyzeError::InvalidDictionary(message) => write!(f, "コードの定義が正しくありません: {}", message),
+            AnalyzeError::SampleRateTooLow(rate) => write!(f, "サンプリング周波数が低すぎます: {}Hz", rate),
+            AnalyzeError::InvalidRange(message) => write!(f, "解析する範囲が正しくありません: {}", message),
+            AnalyzeError::InvalidInput(message) => write!(f, "入力が正しくありません: {}", message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AnalyzeError {}
+
+// 解析は続けられるが、結果が正しくない可能性がある場合の警告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnalyzeWarning {
+    // サンプリング周波数が解析する周波数の範囲に対して低い (ファイルの値と推奨する値)
+    LowSampleRate { sample_rate: u32, recommended: u32 },
+}
+
+impl fmt::Display for AnalyzeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalyzeWarning::LowSampleRate { sample_rate, recommended } => write!(
+                f,
+                "サンプリング周波数が低いため高い音を正しく解析できない可能性があります ({}Hz、{}Hz以上を推奨)",
+                sample_rate, recommended
+            ),
+        }
+    }
+}
+
+impl AnalyzeError {
+    // houndのエラーを対応するAnalyzeErrorに変換する
+    #[cfg(feature = "io")]
+    pub(crate) fn from_hound(error: hound::Error, path: &str) -> Self {
+        match error {
+            hound::Error::IoError(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                AnalyzeError::FileNotFound(path.to_string())
+            },
+            hound::Error::Unsupported | hound::Error::TooWide | hound::Error::InvalidSampleFormat => {
+                AnalyzeError::UnsupportedFormat(error.to_string())
+            },
+            _ => AnalyzeError::DecodeError(error.to_string()),
+        }
+    }
+
+    // symphoniaのエラーを対応するAnalyzeErrorに変換する
+    #[cfg(feature = "decode")]
+    pub(crate) fn from_symphonia(error: symphonia::core::errors::Error, path: &str) -> Self {
+        use symphonia::core::errors::Error;
+        match error {
+            Error::IoError(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                AnalyzeError::FileNotFound(path.to_string())
+            },
+            Error::Unsupported(message) => AnalyzeError::UnsupportedFormat(message.to_string()),
+            _ => AnalyzeError::DecodeError(error.to_string()),
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::ChordSpan;
+use crate::prelude::*;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+// Krumhansl-Kesslerの長調と短調のプロファイル (0が主音)
+const MAJOR_PROFILE: [f64; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f64; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+// 調の主音の音名 (0がC)。調号の少ない方の表記にする
+const MAJOR_TONICS: [&str; 12] = ["C", "Db", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B"];
+const MINOR_TONICS: [&str; 12] = ["C", "C#", "D", "Eb", "E", "F", "F#", "G", "G#", "A", "Bb", "B"];
+
+// 推定した調
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyEstimate {
+    // 主音の音名
+    pub tonic: String,
+    // 短調かどうか
+    pub minor: bool,
+    // プロファイルとの相関係数 (-1.0〜1.0、大きいほど確からしい)
+    pub confidence: f64,
+}
+
+impl KeyEstimate {
+    // AccidentalStyle::for_keyに渡せる形式の調の名前 (例: "Eb"、"F#m")
+    pub fn name(&self) -> String {
+        if self.minor {
+            format!("{}m", self.tonic)
+        } else {
+            self.tonic.clone()
+        }
+    }
+}
+
+impl fmt::Display for KeyEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.tonic, if self.minor { "minor" } else { "major" })
+    }
+}
+
+// 2つの系列のピアソンの相関係数
+fn correlation(a: &[f64; 12], b: &[f64; 12]) -> f64 {
+    let mean_a = a.iter().sum::<f64>() / 12.0;
+    let mean_b = b.iter().sum::<f64>() / 12.0;
+
+    let mut covariance = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+
+    if var_a == 0.0 || var_b == 0.0 {
+        return 0.0;
+    }
+    covariance / (var_a * var_b).sqrt()
+}
+
+// クロマを24個の長調・短調のプロファイルと比べ、確からしい順に並べて返す
+//
+// chromaは0がCの音高クラスごとの強さで、chroma関数の結果をそのまま渡せる。
+pub fn estimate_key(chroma: &[f64; 12]) -> Vec<KeyEstimate> {
+    let mut keys = Vec::with_capacity(24);
+
+    for tonic in 0..12 {
+        for (profile, minor) in [(&MAJOR_PROFILE, false), (&MINOR_PROFILE, true)] {
+            // 主音がCになるようにクロマを回転させてから比べる
+            let mut rotated = [0.0; 12];
+            for (i, x) in rotated.iter_mut().enumerate() {
+                *x = chroma[(tonic + i) % 12];
+            }
+
+            keys.push(KeyEstimate {
+                tonic: if minor { MINOR_TONICS[tonic] } else { MAJOR_TONICS[tonic] }.to_string(),
+                minor,
+                confidence: correlation(&rotated, profile),
+            });
+        }
+    }
+
+    keys.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    keys
+}
+
+// コード進行の構成音を区間の長さで重み付けしてクロマにまとめる
+pub fn progression_chroma(spans: &[ChordSpan]) -> [f64; 12] {
+    let mut chroma = [0.0; 12];
+
+    for span in spans {
+        let duration = (span.end_sec - span.start_sec).max(0.0);
+        for interval in &span.chord.intervals {
+            // MIDIノート番号は0がCなので、そのまま音高クラスになる
+            chroma[(span.chord.root_midi + interval).rem_euclid(12) as usize] += duration;
+        }
+    }
+
+    chroma
+}
+
+// コード進行から調を推定し、確からしい順に並べて返す
+pub fn estimate_key_from_progression(spans: &[ChordSpan]) -> Vec<KeyEstimate> {
+    estimate_key(&progression_chroma(spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{STANDARD_TUNING_HZ, analyze_chord, midi_to_freq};
+
+    // ルート音のMIDIノート番号と音程のコードを1秒ずつ並べた進行
+    fn progression(chords: &[(i32, &[i32])]) -> Vec<ChordSpan> {
+        chords.iter().enumerate().map(|(i, (root, intervals))| {
+            let peaks = intervals.iter().map(|x| midi_to_freq(root + x, STANDARD_TUNING_HZ)).collect();
+            ChordSpan {
+                start_sec: i as f64,
+                end_sec: i as f64 + 1.0,
+                chord: analyze_chord(peaks).unwrap(),
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn i_iv_v7_i_is_c_major() {
+        // C F G7 C
+        let spans = progression(&[(60, &[0, 4, 7]), (65, &[0, 4, 7]), (67, &[0, 4, 7, 10]), (60, &[0, 4, 7])]);
+
+        let keys = estimate_key_from_progression(&spans);
+        assert_eq!(keys[0].to_string(), "C major");
+        assert_eq!(keys[0].name(), "C");
+    }
+
+    #[test]
+    fn minor_progression_is_a_minor() {
+        // Am Dm E Am
+        let spans = progression(&[(57, &[0, 3, 7]), (62, &[0, 3, 7]), (64, &[0, 4, 7]), (57, &[0, 3, 7])]);
+
+        let keys = estimate_key_from_progression(&spans);
+        assert_eq!(keys[0].to_string(), "A minor");
+        assert_eq!(keys[0].name(), "Am");
+    }
+
+    #[test]
+    fn returns_all_keys_sorted_by_confidence() {
+        let spans = progression(&[(60, &[0, 4, 7]), (67, &[0, 4, 7])]);
+        let keys = estimate_key_from_progression(&spans);
+
+        assert_eq!(keys.len(), 24);
+        assert_eq!(keys.iter().filter(|key| key.minor).count(), 12);
+        assert!(keys.windows(2).all(|x| x[0].confidence >= x[1].confidence));
+        assert!(keys.iter().all(|key| (-1.0..=1.0).contains(&key.confidence)));
+    }
+
+    #[test]
+    fn chord_tones_are_weighted_by_duration() {
+        let mut spans = progression(&[(60, &[0, 4, 7])]);
+        spans[0].end_sec = 2.5;
+
+        let chroma = progression_chroma(&spans);
+        assert_eq!((chroma[0], chroma[4], chroma[7]), (2.5, 2.5, 2.5));
+        assert_eq!(chroma.iter().sum::<f64>(), 7.5);
+    }
+
+    #[test]
+    fn empty_progression_has_zero_confidence() {
+        assert_eq!(progression_chroma(&[]), [0.0; 12]);
+
+        let keys = estimate_key_from_progression(&[]);
+        assert_eq!(keys.len(), 24);
+        assert!(keys.iter().all(|key| key.confidence == 0.0));
+    }
+}
@@ -0,0 +1,829 @@
+// stdのfeatureを無効にした場合はno_stdでビルドし、allocだけを使う
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fft")]
+mod analyzer;
+mod chroma;
+#[cfg(feature = "decode")]
+mod decode;
+mod dictionary;
+mod error;
+mod key;
+mod prelude;
+mod progression;
+#[cfg(feature = "fft")]
+mod spectrum;
+mod synth;
+#[cfg(test)]
+mod test_util;
+mod theory;
+#[cfg(feature = "io")]
+mod wave;
+
+#[cfg(feature = "fft")]
+pub use analyzer::Analyzer;
+pub use chroma::{analyze_chord_from_chroma, chroma};
+pub use dictionary::ChordDictionary;
+pub use error::{AnalyzeError, AnalyzeWarning};
+pub use key::{KeyEstimate, estimate_key, estimate_key_from_progression, progression_chroma};
+#[cfg(feature = "fft")]
+pub use progression::analyze_progression;
+pub use progression::{ChordSpan, merge_progression, overlap_hop_size};
+#[cfg(feature = "fft")]
+pub use spectrum::{analyze_detailed, analyze_samples, spectrum};
+#[cfg(feature = "fft")]
+pub(crate) use spectrum::{SILENCE_THRESHOLD, plan_fft, spectrum_with};
+pub use synth::synthesize_chord;
+#[cfg(feature = "io")]
+pub use synth::write_wave;
+pub(crate) use theory::NOTES;
+pub use theory::{
+    AccidentalStyle, ChordResult, DetectedNote, IntervalMode, STANDARD_TUNING_HZ, analyze_chord, analyze_chord_candidates,
+    analyze_chord_candidates_with_mode, analyze_chord_weighted, analyze_chord_with_dictionary, analyze_chord_with_mode,
+    analyze_chord_with_tolerance, detect_notes, freq_to_midi, get_cents, get_note, midi_to_freq,
+};
+#[cfg(all(feature = "io", feature = "fft"))]
+pub use wave::{analyze_file, analyze_file_detailed, analyze_file_with};
+#[cfg(feature = "io")]
+pub use wave::{get_audio, get_wave, is_supported_extension, validate_spec};
+
+// 窓関数の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowKind {
+    #[default]
+    Hamming,
+    Hann,
+    Blackman,
+    BlackmanHarris,
+    // 窓関数を適用しない
+    Rectangular,
+}
+
+impl WindowKind {
+    // 長さlenの窓におけるi番目の係数を返す
+    fn coefficient(&self, i: usize, len: usize) -> f64 {
+        let t = 2.0 * core::f64::consts::PI * i as f64 / len as f64;
+        match self {
+            WindowKind::Hamming => 0.54 - 0.46 * t.cos(),
+            WindowKind::Hann => 0.5 - 0.5 * t.cos(),
+            WindowKind::Blackman => 0.42 - 0.5 * t.cos() + 0.08 * (2.0 * t).cos(),
+            WindowKind::BlackmanHarris => {
+                0.35875 - 0.48829 * t.cos() + 0.14128 * (2.0 * t).cos() - 0.01168 * (3.0 * t).cos()
+            },
+            WindowKind::Rectangular => 1.0,
+        }
+    }
+}
+
+// 信号に窓関数を適用する
+pub fn apply_window(samples: &[f64], window: WindowKind) -> Vec<f64> {
+    samples.iter().enumerate().map(|(i, x)| x * window.coefficient(i, samples.len())).collect()
+}
+
+// インターリーブされた各チャンネルのサンプルを平均してモノラルにする
+pub fn downmix(samples: &[f64], channels: u16) -> Vec<f64> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+
+    samples
+        .chunks_exact(channels as usize)
+        .map(|frame| frame.iter().sum::<f64>() / channels as f64)
+        .collect()
+}
+
+// ダウンサンプリング前にかける低域通過フィルタの片側のタップ数
+const RESAMPLE_HALF_TAPS: usize = 32;
+
+// 低域通過フィルタの遮断周波数の、変換後のナイキスト周波数に対する比
+//
+// フィルタの遷移帯域の分だけ低くし、変換後のナイキスト周波数より上の成分が残らないようにする。
+const RESAMPLE_CUTOFF_RATIO: f64 = 0.9;
+
+// Blackman窓をかけたsinc関数で低域通過フィルタをかける
+//
+// cutoffはサンプリング周波数に対する遮断周波数の比 (0.5未満)。端はその外側も端の値が続くものとして扱う。
+fn low_pass(samples: &[f64], cutoff: f64) -> Vec<f64> {
+    let half = RESAMPLE_HALF_TAPS as isize;
+    let kernel: Vec<f64> = (-half..=half).map(|n| {
+        let sinc = if n == 0 {
+            2.0 * cutoff
+        } else {
+            (2.0 * core::f64::consts::PI * cutoff * n as f64).sin() / (core::f64::consts::PI * n as f64)
+        };
+        sinc * WindowKind::Blackman.coefficient((n + half) as usize, 2 * RESAMPLE_HALF_TAPS)
+    }).collect();
+    // 直流成分の利得を1にする
+    let gain: f64 = kernel.iter().sum();
+
+    let last = samples.len() as isize - 1;
+    (0..samples.len() as isize).map(|i| {
+        kernel.iter().enumerate().map(|(k, c)| c * samples[(i + k as isize - half).clamp(0, last) as usize]).sum::<f64>() / gain
+    }).collect()
+}
+
+// 信号のサンプリング周波数をfromからtoに変換する
+//
+// 隣り合うサンプルの線形補間で求める。ダウンサンプリングの場合は折り返しを防ぐため、
+// 先に変換後のナイキスト周波数より上の成分を低域通過フィルタで取り除く。
+// どちらかのサンプリング周波数が0の場合はSampleRateTooLowを返す。
+pub fn resample(samples: &[f64], from: u32, to: u32) -> Result<Vec<f64>, AnalyzeError> {
+    if from == 0 || to == 0 {
+        return Err(AnalyzeError::SampleRateTooLow(0));
+    }
+    if from == to || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+
+    let filtered = if to < from {
+        low_pass(samples, 0.5 * RESAMPLE_CUTOFF_RATIO * to as f64 / from as f64)
+    } else {
+        samples.to_vec()
+    };
+
+    let ratio = from as f64 / to as f64;
+    let len = (samples.len() as f64 / ratio).round() as usize;
+    Ok((0..len)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position.floor() as usize;
+            let t = position - index as f64;
+            let current = filtered[index.min(filtered.len() - 1)];
+            let next = filtered[(index + 1).min(filtered.len() - 1)];
+            current + (next - current) * t
+        })
+        .collect())
+}
+
+// サンプル数からゼロ埋め後のFFTの長さを求める
+//
+// 2の累乗にすることでFFTが速くなり、ビンの間隔も細かくなる。
+pub fn fft_len(samples: usize) -> usize {
+    samples.next_power_of_two()
+}
+
+// ビン番号を周波数 (Hz) に変換する
+//
+// 半分に切り詰めたスペクトルの長さではなく、ゼロ埋め後のFFTの長さで割る。
+pub fn bin_to_freq(bin: f64, fft_len: usize, sample_rate: u32) -> f64 {
+    bin / fft_len as f64 * sample_rate as f64
+}
+
+// 周波数 (Hz) をビン番号に変換する
+pub fn freq_to_bin(freq: f64, fft_len: usize, sample_rate: u32) -> f64 {
+    freq / sample_rate as f64 * fft_len as f64
+}
+
+// スペクトルを移動平均で平滑化する
+//
+// ピークの位置がずれないよう、各ビンの前後width / 2個ずつを含めた平均を取る。
+// 偶数のwidthは1つ大きい奇数として扱い、0と1の場合は平滑化しない。
+// 端のビンは範囲内にあるビンだけで平均を取る。
+pub fn smooth(output: &[f64], width: usize) -> Vec<f64> {
+    let half = width / 2;
+    if half == 0 {
+        return output.to_vec();
+    }
+
+    (0..output.len()).map(|i| {
+        let window = &output[i.saturating_sub(half)..(i + half + 1).min(output.len())];
+        window.iter().sum::<f64>() / window.len() as f64
+    }).collect()
+}
+
+// スペクトルからピークを取得し、振幅の大きい順に最大max_peaks個返す
+//
+// 最大のピークの振幅のthreshold_ratio倍に満たないピークはノイズとして除く。
+pub fn find_peaks(output: &[f64], max_peaks: usize, threshold_ratio: f64) -> Vec<(usize, f64)> {
+    let mut peaks: Vec<(usize, f64)> = output.iter().enumerate().zip(output.iter().skip(1)).zip(output.iter().skip(2)).filter_map(|(((i, x), y), z)| {
+        // 一個手前と一個後ろの値より大きい場合にピークとして取得
+        if y > x && y > z {
+            Some((i + 1, *y))
+        } else {
+            None
+        }
+    }).collect();
+
+    // ピークの中から上位max_peaks個を取得
+    peaks.sort_by(|a, b| b.1.total_cmp(&a.1));
+    peaks.truncate(max_peaks);
+
+    if let Some(&(_, max)) = peaks.first() {
+        peaks.retain(|x| x.1 >= max * threshold_ratio);
+    }
+
+    peaks
+}
+
+// 倍音とみなすずれの許容範囲 (セント)
+const HARMONIC_TOLERANCE_CENTS: f64 = 50.0;
+
+// 倍音とみなす最大の次数
+const MAX_HARMONIC: usize = 8;
+
+// n次の倍音の振幅は基本周波数の振幅のこの値 / n倍までとみなす
+//
+// のこぎり波の倍音は1 / nで減衰するので、それより少し強いものまで倍音として扱う。
+const HARMONIC_MAGNITUDE_RATIO: f64 = 1.5;
+
+// 強いピークの整数倍の位置にある弱いピークを倍音として取り除く
+//
+// 1倍 (同じ音の漏れ) も含めて取り除く。
+// 整数倍の位置にあっても、倍音として想定される振幅より強いピークは別の音として残す
+// (ワイドなボイシングの5度や3度を倍音と誤らないようにする)。
+// peaksは振幅の大きい順に並んだ周波数と振幅の組で、順番を保ったまま返す。
+pub fn remove_harmonics(peaks: &[(f64, f64)], tolerance_cents: f64) -> Vec<(f64, f64)> {
+    let mut kept: Vec<(f64, f64)> = Vec::new();
+
+    for &(freq, magnitude) in peaks {
+        let is_harmonic = kept.iter().any(|&(fundamental, fundamental_magnitude)| {
+            (1..=MAX_HARMONIC).any(|n| {
+                (1200.0 * (freq / (fundamental * n as f64)).log2()).abs() <= tolerance_cents
+                    && (n == 1 || magnitude < fundamental_magnitude * HARMONIC_MAGNITUDE_RATIO / n as f64)
+            })
+        });
+
+        if !is_harmonic {
+            kept.push((freq, magnitude));
+        }
+    }
+
+    kept
+}
+
+// 放物線補間でピークの正確な位置を求め、小数のビン番号で返す
+//
+// ピークのビンと前後のビンの対数振幅を通る放物線の頂点をピークの位置とする。
+pub fn interpolate_peak(output: &[f64], bin: usize) -> f64 {
+    if bin == 0 || bin + 1 >= output.len() {
+        return bin as f64;
+    }
+
+    let (a, b, c) = (output[bin - 1].ln(), output[bin].ln(), output[bin + 1].ln());
+    let denominator = a - 2.0 * b + c;
+    if !denominator.is_finite() || denominator == 0.0 {
+        return bin as f64;
+    }
+
+    bin as f64 + 0.5 * (a - c) / denominator
+}
+
+// 倍音積スペクトルを計算する
+//
+// スペクトルを1/2, 1/3, ...に間引いたものを掛け合わせ、基本周波数のビンを強調する。
+// harmonicsは掛け合わせる倍音の数で、結果の長さは output.len() / harmonics になる。
+pub fn harmonic_product_spectrum(output: &[f64], harmonics: usize) -> Vec<f64> {
+    let harmonics = harmonics.max(1);
+    (0..output.len() / harmonics).map(|i| {
+        (1..=harmonics).map(|h| output[i * h]).product()
+    }).collect()
+}
+
+// 倍音積スペクトルから基本周波数を推定する
+pub fn fundamental_frequency(output: &[f64], sample_rate: u32, fft_len: usize) -> Option<f64> {
+    let hps = harmonic_product_spectrum(output, 4);
+
+    // 直流成分は除いて最大のビンを探す
+    let bin = hps.iter().enumerate().skip(1)
+        .max_by(|a, b| a.1.total_cmp(b.1))?
+        .0;
+
+    Some(bin_to_freq(interpolate_peak(&hps, bin), fft_len, sample_rate))
+}
+
+// サンプリング周波数が解析する周波数の範囲に対して低すぎる場合の扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SampleRateCheck {
+    // 何もしない
+    Ignore,
+    // 警告を解析結果に含めて解析を続ける
+    #[default]
+    Warn,
+    // SampleRateTooLowを返す
+    Error,
+}
+
+// 解析の設定
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzeOptions {
+    // 窓関数の種類
+    pub window: WindowKind,
+    // 倍音積スペクトルで求めた基本周波数をルート音の推定に使う
+    pub harmonic_product_spectrum: bool,
+    // 強いピークの倍音とみなせる弱いピークをコードの構成音から除く
+    pub remove_harmonics: bool,
+    // コードの構成音の候補とするピークの最大数
+    pub max_peaks: usize,
+    // 解析する周波数の下限 (Hz)、これより低いピークはコードの構成音にしない
+    pub min_frequency_hz: f64,
+    // 解析する周波数の上限 (Hz)
+    pub max_frequency_hz: f64,
+    // スペクトルを平滑化する移動平均の幅 (ビン数、1以下で平滑化しない)
+    pub smoothing_width: usize,
+    // 最大のピークに対する振幅の比がこれより小さいピークはノイズとして除く
+    pub peak_threshold_ratio: f64,
+    // 音名の派生音の表記
+    pub accidental_style: AccidentalStyle,
+    // 基準音A4の周波数 (Hz)
+    pub tuning_hz: f64,
+    // コードの辞書
+    pub dictionary: ChordDictionary,
+    // 解析前にこのサンプリング周波数へ変換する (Noneの場合は元のまま)
+    pub resample_hz: Option<u32>,
+    // 1オクターブより上の音程の扱い方
+    pub interval_mode: IntervalMode,
+    // サンプリング周波数が低すぎる場合の扱い
+    pub sample_rate_check: SampleRateCheck,
+    // ファイルのこの時刻 (秒) から解析する
+    pub start_sec: f64,
+    // 解析する長さ (秒、Noneの場合はファイルの終わりまで)
+    pub duration_sec: Option<f64>,
+}
+
+impl Default for AnalyzeOptions {
+    fn default() -> Self {
+        AnalyzeOptions {
+            window: WindowKind::default(),
+            harmonic_product_spectrum: false,
+            remove_harmonics: true,
+            max_peaks: 8,
+            min_frequency_hz: LOWEST_NOTE_HZ,
+            max_frequency_hz: 20000.0,
+            smoothing_width: 3,
+            peak_threshold_ratio: 0.05,
+            accidental_style: AccidentalStyle::default(),
+            tuning_hz: STANDARD_TUNING_HZ,
+            dictionary: ChordDictionary::default(),
+            resample_hz: None,
+            interval_mode: IntervalMode::default(),
+            sample_rate_check: SampleRateCheck::default(),
+            start_sec: 0.0,
+            duration_sec: None,
+        }
+    }
+}
+
+// 解析する最も低い音 (A0) の周波数
+const LOWEST_NOTE_HZ: f64 = 27.5;
+
+// 解析する最も高い音 (C8) の周波数
+const HIGHEST_NOTE_HZ: f64 = 4186.0;
+
+// 解析する周波数の範囲を表現するのに必要なサンプリング周波数を返す
+pub fn required_sample_rate(options: &AnalyzeOptions) -> u32 {
+    (2.0 * options.max_frequency_hz.min(HIGHEST_NOTE_HZ)).ceil() as u32
+}
+
+// 信号からstart_sec秒からduration_sec秒間の部分を切り出す (duration_secがNoneの場合は終わりまで)
+//
+// 範囲が負の場合や信号の長さを超える場合、長さが0以下の場合はInvalidRangeを返す。
+// 開始位置はその時刻を含むサンプルとし、短い範囲でも少なくとも1サンプルは含める。
+pub fn select_range(samples: &[f64], sample_rate: u32, start_sec: f64, duration_sec: Option<f64>) -> Result<&[f64], AnalyzeError> {
+    let length_sec = samples.len() as f64 / sample_rate as f64;
+    let out_of_range = || {
+        let range = match duration_sec {
+            Some(duration) => format!("開始{}秒、長さ{}秒", start_sec, duration),
+            None => format!("開始{}秒", start_sec),
+        };
+        AnalyzeError::InvalidRange(format!("{} (音声の長さは{:.3}秒)", range, length_sec))
+    };
+
+    if !(start_sec >= 0.0 && start_sec < length_sec) {
+        return Err(out_of_range());
+    }
+    // 切り捨てるので、終わりの直前の時刻でも信号の長さを超えない
+    let start = (start_sec * sample_rate as f64).floor() as usize;
+    if start >= samples.len() {
+        return Err(out_of_range());
+    }
+
+    let end = match duration_sec {
+        Some(duration) => {
+            if !(duration > 0.0 && start_sec + duration <= length_sec) {
+                return Err(out_of_range());
+            }
+            ((start_sec + duration) * sample_rate as f64).round() as usize
+        },
+        None => samples.len(),
+    };
+
+    // start < samples.len()なので、丸めにより空にならないように1サンプルを足しても範囲内に収まる
+    Ok(&samples[start..end.min(samples.len()).max(start + 1)])
+}
+
+// 最大のピークに対する振幅の比がこれ以上のピークは重みを1とする
+//
+// これより弱いピークはエネルギー (振幅の2乗) の比に応じて軽くするため、
+// 比が0.1 (-20dB) 程度より小さいピークは余分な音として数えてもコードの判定をほとんど変えない。
+const FULL_WEIGHT_RATIO: f64 = 0.15;
+
+// 解析の途中結果を含めたコードの解析結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisDetail {
+    pub chord: ChordResult,
+    // 平滑化し、上限の周波数で切り詰めた振幅スペクトル
+    pub spectrum: Vec<f64>,
+    // spectrumの各ビンの周波数 (Hz)
+    pub frequencies: Vec<f64>,
+    // コードの判定に使ったピークの周波数 (Hz) と振幅
+    pub peaks: Vec<(f64, f64)>,
+    // 解析中に見つかった警告
+    pub warnings: Vec<AnalyzeWarning>,
+}
+
+// 振幅スペクトルを解析し、途中結果を含めた解析結果を返す (fft_lenはゼロ埋め後のFFTの長さ)
+//
+// outputは長さfft_len / 2の振幅スペクトルで、fftのfeatureを無効にした場合は別の方法で求めたものを渡せる。
+// サンプリング周波数が0の場合はSampleRateTooLowを返す。
+pub fn analyze_spectrum(output: Vec<f64>, sample_rate: u32, fft_len: usize, options: &AnalyzeOptions) -> Result<AnalysisDetail, AnalyzeError> {
+    if sample_rate == 0 {
+        return Err(AnalyzeError::SampleRateTooLow(0));
+    }
+
+    // 波形を平滑化
+    let mut output = smooth(&output, options.smoothing_width);
+
+    // 上限の周波数より高いビンを高周波ノイズとして除去
+    let max_bin = freq_to_bin(options.max_frequency_hz, fft_len, sample_rate).ceil() as usize;
+    output.truncate(max_bin);
+
+    // 下限の周波数より低いビンは直流成分や切り替わりの雑音としてピークを探さない
+    let min_bin = (freq_to_bin(options.min_frequency_hz, fft_len, sample_rate).floor() as usize).min(output.len());
+    let peaks: Vec<(usize, f64)> = find_peaks(&output[min_bin..], options.max_peaks, options.peak_threshold_ratio)
+        .into_iter()
+        .map(|(bin, magnitude)| (bin + min_bin, magnitude))
+        .collect();
+
+    let main_freq: Vec<(f64, f64)> = peaks.iter().map(|x| (bin_to_freq(interpolate_peak(&output, x.0), fft_len, sample_rate), x.1)).collect();
+
+    // 倍音をコードの構成音として数えないように取り除く
+    let main_freq = if options.remove_harmonics {
+        remove_harmonics(&main_freq, HARMONIC_TOLERANCE_CENTS)
+    } else {
+        main_freq
+    };
+    let mut main_freq: Vec<f64> = main_freq.into_iter().map(|x| x.0).collect();
+
+    let fundamental = if options.harmonic_product_spectrum {
+        fundamental_frequency(&output, sample_rate, fft_len)
+    } else {
+        None
+    };
+    if let Some(fundamental) = fundamental {
+        // 基本周波数より低いピークはノイズとみなし、基本周波数を最低音にする
+        main_freq.retain(|x| (x / fundamental).log2() * 12.0 > -0.5);
+        main_freq.push(fundamental);
+    }
+
+    // 最大のピークに対する振幅の比から各ピークの重みを求める
+    let magnitudes: Vec<f64> = main_freq.iter().map(|&freq| magnitude_at(&output, freq, fft_len, sample_rate)).collect();
+    let max = magnitudes.iter().fold(0.0, |m: f64, v| v.max(m));
+    let mut weights: Vec<f64> = magnitudes.iter().map(|x| if max > 0.0 { (x / max / FULL_WEIGHT_RATIO).min(1.0).powi(2) } else { 1.0 }).collect();
+    if fundamental.is_some() {
+        // 基本周波数はスペクトル上で弱くても最低音として扱う
+        if let Some(weight) = weights.last_mut() {
+            *weight = 1.0;
+        }
+    }
+
+    // 基準音が440Hzになるように周波数を換算してから解析する
+    let scale = STANDARD_TUNING_HZ / options.tuning_hz;
+    let scaled: Vec<f64> = main_freq.iter().map(|x| x * scale).collect();
+
+    let mut result = ChordResult {
+        notes: detect_notes(&main_freq, options.accidental_style, options.tuning_hz),
+        peaks: main_freq,
+        ..analyze_chord_weighted(scaled, &weights, 0, &options.dictionary, options.interval_mode)?
+            .with_accidentals(options.accidental_style)
+    };
+
+    // 構成音以外のピークが強いほど確からしさを下げる
+    result.confidence *= chord_energy_ratio(&result, &output, sample_rate, fft_len);
+
+    let peaks = result.peaks.iter().map(|&freq| (freq, magnitude_at(&output, freq, fft_len, sample_rate))).collect();
+    let frequencies = (0..output.len()).map(|i| bin_to_freq(i as f64, fft_len, sample_rate)).collect();
+    Ok(AnalysisDetail {
+        chord: result,
+        spectrum: output,
+        frequencies,
+        peaks,
+        warnings: Vec::new(),
+    })
+}
+
+// 周波数に最も近いビンの振幅を返す
+fn magnitude_at(output: &[f64], freq: f64, fft_len: usize, sample_rate: u32) -> f64 {
+    let bin = freq_to_bin(freq, fft_len, sample_rate).round() as usize;
+    output.get(bin).copied().unwrap_or(0.0)
+}
+
+// ピークのエネルギーのうち、コードの構成音と最低音に当たるピークの割合を返す
+fn chord_energy_ratio(result: &ChordResult, output: &[f64], sample_rate: u32, fft_len: usize) -> f64 {
+    let mut tones: Vec<i32> = result.intervals.iter().map(|x| (result.root_midi + x).rem_euclid(12)).collect();
+    if let Some(bass) = result.notes.iter().min_by(|a, b| a.freq.total_cmp(&b.freq)) {
+        tones.push(bass.midi.rem_euclid(12));
+    }
+
+    let mut total = 0.0;
+    let mut chord = 0.0;
+    for note in &result.notes {
+        let energy = magnitude_at(output, note.freq, fft_len, sample_rate).powi(2);
+        total += energy;
+        if tones.contains(&note.midi.rem_euclid(12)) {
+            chord += energy;
+        }
+    }
+
+    if total > 0.0 { chord / total } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_coefficients() {
+        let samples = vec![1.0; 8];
+
+        // 矩形窓は信号をそのまま返す
+        assert_eq!(apply_window(&samples, WindowKind::Rectangular), samples);
+        // ハン窓は端が0になる
+        assert_eq!(apply_window(&samples, WindowKind::Hann)[0], 0.0);
+        // 既定はハミング窓
+        assert_eq!(WindowKind::default(), WindowKind::Hamming);
+        assert!((apply_window(&samples, WindowKind::Hamming)[0] - 0.08).abs() < 1e-12);
+    }
+
+    #[test]
+    fn downmix_averages_interleaved_channels() {
+        assert_eq!(downmix(&[1.0, 0.0, 0.5, -0.5, -1.0, 1.0], 2), [0.5, 0.0, 0.0]);
+        assert_eq!(downmix(&[0.3, 0.6, 0.9], 3), [0.6]);
+        assert_eq!(downmix(&[0.1, 0.2], 1), [0.1, 0.2]);
+    }
+
+    // 周波数のずれ (セント)
+    #[cfg(feature = "fft")]
+    fn cents_between(a: f64, b: f64) -> f64 {
+        1200.0 * (a / b).log2()
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn interpolation_recovers_tone_between_bins() {
+        let sample_rate = 44100;
+        let len = 8192;
+        // ビンとビンのちょうど中間の周波数
+        let freq = bin_to_freq(82.5, len, sample_rate);
+        let samples = apply_window(&test_util::sine(&[freq], sample_rate, len), WindowKind::Hann);
+
+        let output = spectrum(&samples);
+        let (bin, _) = find_peaks(&output, 1, 0.0)[0];
+        let estimated = bin_to_freq(interpolate_peak(&output, bin), len, sample_rate);
+
+        assert!(cents_between(bin_to_freq(bin as f64, len, sample_rate), freq).abs() > 5.0);
+        assert!(cents_between(estimated, freq).abs() < 1.0, "{}Hz", estimated);
+    }
+
+    #[test]
+    fn find_peaks_ignores_nan_magnitudes() {
+        let output = [0.0, 2.0, 0.0, f64::NAN, 0.0, 3.0, 0.0];
+
+        assert_eq!(find_peaks(&output, 8, 0.0), [(5, 3.0), (1, 2.0)]);
+    }
+
+    #[test]
+    fn interpolation_keeps_edge_bins() {
+        assert_eq!(interpolate_peak(&[3.0, 1.0, 2.0], 0), 0.0);
+        assert_eq!(interpolate_peak(&[1.0, 2.0, 3.0], 2), 2.0);
+        // 左右対称なピークはずれない
+        assert_eq!(interpolate_peak(&[1.0, 4.0, 1.0], 1), 1.0);
+    }
+
+    // 各倍音の振幅を指定した信号 (amplitudes[0]が基本周波数)
+    #[cfg(feature = "fft")]
+    fn harmonic_tone(fundamental: f64, amplitudes: &[f64], sample_rate: u32, len: usize) -> Vec<f64> {
+        (0..len)
+            .map(|i| {
+                let t = i as f64 / sample_rate as f64;
+                amplitudes.iter().enumerate()
+                    .map(|(n, a)| a * (2.0 * std::f64::consts::PI * fundamental * (n + 1) as f64 * t).sin())
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn harmonic_product_spectrum_finds_weak_fundamental() {
+        let sample_rate = 44100;
+        // 基本周波数が第2倍音より弱いのこぎり波
+        let samples = harmonic_tone(110.0, &[0.3, 1.0, 0.33, 0.25, 0.2], sample_rate, 16384);
+        let samples = apply_window(&samples, WindowKind::Hann);
+        let output = spectrum(&samples);
+
+        let (strongest, _) = find_peaks(&output, 1, 0.0)[0];
+        assert!((bin_to_freq(strongest as f64, samples.len(), sample_rate) - 220.0).abs() < 3.0);
+
+        let fundamental = fundamental_frequency(&output, sample_rate, samples.len()).unwrap();
+        assert!(cents_between(fundamental, 110.0).abs() < 10.0, "{}Hz", fundamental);
+
+        let options = AnalyzeOptions { harmonic_product_spectrum: true, ..AnalyzeOptions::default() };
+        let result = analyze_samples(&samples, sample_rate, &options).unwrap();
+        assert_eq!(format!("{}{}", result.root_note, result.octave), "A2");
+    }
+
+    #[test]
+    fn fundamental_frequency_tolerates_nan() {
+        let mut output = vec![0.0; 64];
+        output[10] = f64::NAN;
+
+        // NaNが含まれていてもパニックしない
+        assert!(fundamental_frequency(&output, 44100, 128).is_some());
+    }
+
+    #[test]
+    fn remove_harmonics_keeps_open_voicing() {
+        let (c3, g4, e5) = (130.81, 392.0, 659.26);
+
+        // G4はC3の第3倍音、E5は第5倍音に近いが、同じくらい強いので構成音として残す
+        let kept = remove_harmonics(&[(c3, 1.0), (g4, 0.9), (e5, 0.8)], HARMONIC_TOLERANCE_CENTS);
+        assert_eq!(kept.len(), 3);
+
+        // 倍音として想定される振幅より弱い場合は取り除く
+        let kept = remove_harmonics(&[(c3, 1.0), (g4, 0.3), (e5, 0.2)], HARMONIC_TOLERANCE_CENTS);
+        assert_eq!(kept, [(c3, 1.0)]);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn single_note_with_harmonics_is_not_a_chord() {
+        let sample_rate = 44100;
+        let samples = harmonic_tone(261.63, &[1.0, 0.5, 0.33, 0.25, 0.2], sample_rate, 16384);
+        let samples = apply_window(&samples, WindowKind::Hamming);
+
+        let result = analyze_samples(&samples, sample_rate, &AnalyzeOptions::default()).unwrap();
+        assert_eq!(result.quality, "");
+        assert_eq!(result.notes.len(), 1);
+        assert_eq!(result.notes[0].note, "C4");
+
+        // 倍音を除かない場合は倍音もピークとして残る
+        let options = AnalyzeOptions { remove_harmonics: false, ..AnalyzeOptions::default() };
+        let result = analyze_samples(&samples, sample_rate, &options).unwrap();
+        assert!(result.notes.len() > 1);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn open_voiced_triad_is_recognized() {
+        let sample_rate = 44100;
+        let samples = test_util::sine(&[130.81, 392.0, 659.26], sample_rate, 16384);
+        let samples = apply_window(&samples, WindowKind::Hamming);
+
+        let result = analyze_samples(&samples, sample_rate, &AnalyzeOptions::default()).unwrap();
+        assert_eq!(result.to_string(), "C3 major");
+    }
+
+    #[test]
+    fn find_peaks_keeps_strongest_max_peaks() {
+        let output = [0.0, 1.0, 0.0, 3.0, 0.0, 2.0, 0.0];
+
+        assert_eq!(find_peaks(&output, 2, 0.0), [(3, 3.0), (5, 2.0)]);
+        assert_eq!(find_peaks(&output, 8, 0.0).len(), 3);
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn threshold_drops_noise_peaks() {
+        let sample_rate = 44100;
+        let len = 16384;
+
+        // 再現できるように線形合同法で作った白色雑音を小さな正弦波に加える
+        let mut seed: u64 = 1;
+        let samples: Vec<f64> = test_util::sine(&[440.0], sample_rate, len).iter().map(|x| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            0.1 * x + 0.01 * ((seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5)
+        }).collect();
+        let output = spectrum(&apply_window(&samples, WindowKind::Hamming));
+
+        assert_eq!(find_peaks(&output, 8, 0.0).len(), 8);
+
+        let peaks = find_peaks(&output, 8, AnalyzeOptions::default().peak_threshold_ratio);
+        assert_eq!(peaks.len(), 1);
+        assert!((bin_to_freq(peaks[0].0 as f64, len, sample_rate) - 440.0).abs() < 3.0);
+    }
+
+    #[test]
+    fn smooth_with_width_one_leaves_spectrum_untouched() {
+        let output = [0.0, 1.0, 5.0, 2.0, 0.5];
+
+        assert_eq!(smooth(&output, 0), output);
+        assert_eq!(smooth(&output, 1), output);
+    }
+
+    #[test]
+    fn smooth_is_symmetric_and_keeps_peak_position() {
+        let output = [0.0, 0.0, 3.0, 6.0, 3.0, 0.0, 0.0];
+        let smoothed = smooth(&output, 3);
+
+        assert_eq!(smoothed, [0.0, 1.0, 3.0, 4.0, 3.0, 1.0, 0.0]);
+        // 偶数の幅は1つ大きい奇数として扱う
+        assert_eq!(smooth(&output, 2), smoothed);
+        // 端のビンは範囲内のビンだけで平均する
+        assert_eq!(smooth(&[3.0, 0.0, 0.0], 3), [1.5, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn bin_frequency_uses_padded_fft_length() {
+        assert_eq!(bin_to_freq(1024.0, 4096, 44100), 11025.0);
+        assert_eq!(freq_to_bin(11025.0, 4096, 44100), 1024.0);
+    }
+
+    // 信号の二乗平均平方根
+    fn rms(samples: &[f64]) -> f64 {
+        (samples.iter().map(|x| x * x).sum::<f64>() / samples.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn resample_rejects_zero_rates() {
+        assert!(matches!(resample(&[0.0; 4], 0, 44100), Err(AnalyzeError::SampleRateTooLow(0))));
+        assert!(matches!(resample(&[0.0; 4], 44100, 0), Err(AnalyzeError::SampleRateTooLow(0))));
+    }
+
+    #[test]
+    fn resample_changes_length_and_keeps_same_rate() {
+        let samples = test_util::sine(&[440.0], 48000, 48000);
+
+        assert_eq!(resample(&samples, 48000, 48000).unwrap(), samples);
+        assert_eq!(resample(&samples, 48000, 44100).unwrap().len(), 44100);
+        assert_eq!(resample(&samples, 48000, 96000).unwrap().len(), 96000);
+    }
+
+    #[test]
+    fn downsampling_removes_content_above_new_nyquist() {
+        // 44.1kHzのナイキスト周波数より上の30kHzは、フィルタがないと14.1kHzに折り返す
+        let high = resample(&test_util::sine(&[30000.0], 96000, 9600), 96000, 44100).unwrap();
+        assert!(rms(&high) < 0.01, "{}", rms(&high));
+
+        // 帯域内の音はほとんど変わらない
+        let low = resample(&test_util::sine(&[1000.0], 96000, 9600), 96000, 44100).unwrap();
+        assert!((rms(&low) - 0.5f64.sqrt()).abs() < 0.01, "{}", rms(&low));
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn resampled_tones_give_same_note() {
+        let options = AnalyzeOptions::default();
+        let analyze = |samples: Vec<f64>| {
+            let samples = apply_window(&samples, WindowKind::Hamming);
+            analyze_samples(&samples, 44100, &options).unwrap().notes
+        };
+
+        // どちらも同じ約0.34秒の長さにする
+        let from_48k = analyze(resample(&test_util::sine(&[440.0], 48000, 16384), 48000, 44100).unwrap());
+        let from_44k = analyze(test_util::sine(&[440.0], 44100, 15053));
+
+        assert_eq!(from_48k[0].note, "A4");
+        assert_eq!(from_48k[0].note, from_44k[0].note);
+        assert!((from_48k[0].freq - from_44k[0].freq).abs() < 0.5);
+    }
+
+    #[test]
+    fn select_range_near_end_of_signal() {
+        let samples = [0.1; 100];
+
+        // 丸めると信号の長さになる開始時刻でもパニックしない
+        assert_eq!(select_range(&samples, 100, 0.996, None).unwrap().len(), 1);
+        assert_eq!(select_range(&samples, 100, 0.99, Some(0.01)).unwrap().len(), 1);
+        // 終わりちょうどから始まる範囲は空なのでエラーにする
+        assert!(matches!(select_range(&samples, 100, 1.0, None), Err(AnalyzeError::InvalidRange(_))));
+        assert!(matches!(select_range(&samples, 100, 0.5, Some(0.6)), Err(AnalyzeError::InvalidRange(_))));
+    }
+
+    #[test]
+    fn select_range_with_zero_or_tiny_duration() {
+        let samples: Vec<f64> = (0..100).map(|x| x as f64).collect();
+
+        assert!(matches!(select_range(&samples, 100, 0.5, Some(0.0)), Err(AnalyzeError::InvalidRange(_))));
+        assert!(matches!(select_range(&samples, 100, 0.5, Some(-1.0)), Err(AnalyzeError::InvalidRange(_))));
+        // 1サンプルに満たない長さでも開始位置のサンプルを返す
+        assert_eq!(select_range(&samples, 100, 0.5, Some(1e-9)).unwrap(), [50.0]);
+        assert_eq!(select_range(&samples, 100, 0.999, Some(1e-9)).unwrap(), [99.0]);
+    }
+
+    #[test]
+    fn select_range_slices_requested_region() {
+        let samples: Vec<f64> = (0..100).map(|x| x as f64).collect();
+
+        assert_eq!(select_range(&samples, 100, 0.0, None).unwrap().len(), 100);
+        assert_eq!(select_range(&samples, 100, 0.25, Some(0.5)).unwrap(), &samples[25..75]);
+        assert_eq!(select_range(&samples, 100, 0.0, Some(1.0)).unwrap().len(), 100);
+        assert!(matches!(select_range(&samples, 100, -0.1, None), Err(AnalyzeError::InvalidRange(_))));
+        assert!(matches!(select_range(&samples, 100, f64::NAN, None), Err(AnalyzeError::InvalidRange(_))));
+        assert!(matches!(select_range(&[], 100, 0.0, None), Err(AnalyzeError::InvalidRange(_))));
+    }
+}
@@ -1,157 +1,229 @@
-use hound::{self, WavSpec};
-use rustfft::{FftPlanner, num_complex::Complex};
-use std::collections::HashMap;
-
-// Wavファイルを読み込み、窓関数を適用したデータを返す
-fn get_wave(path: &str) -> Result<(WavSpec, Vec<f64>), Box<dyn std::error::Error>> {
-    let mut target = hound::WavReader::open(path)?;
-
-    let spec = target.spec();
-
-    let samples = match spec.sample_format {
-        hound::SampleFormat::Int => {
-            match spec.bits_per_sample {
-                16 => {
-                    target
-                        .samples::<i16>()
-                        .map(|s| s.unwrap() as f64 / (1 << (spec.bits_per_sample - 1)) as f64)
-                        .collect::<Vec<f64>>()
-                },
-                _ => {
-                    target
-                        .samples::<i32>()
-                        .map(|s| s.unwrap() as f64 / (1 << (spec.bits_per_sample - 1)) as f64)
-                        .collect::<Vec<f64>>()
-                }
-            }
-        },
-        hound::SampleFormat::Float => {
-            // 32bit floatの場合
-            target
-                .samples::<f32>()
-                .map(|s| s.unwrap() as f64)
-                .collect::<Vec<f64>>()
-        },
-    };
-
-    // ハミング窓を適用
-    let hamming_window: Vec<f64> = (0..samples.len())
-        .map(|i| {
-            0.54 - 0.46 * (2.0 * std::f64::consts::PI * i as f64 / samples.len() as f64).cos()
-        }).collect();
-    let samples: Vec<f64> = samples.iter().zip(hamming_window.iter()).map(|(x, y)| x * y).collect();
-
-    Ok((spec, samples))
+use signal::{
+    AnalysisDetail, AnalyzeOptions, ChordDictionary, ChordResult, WindowKind, analyze_file_detailed,
+    is_supported_extension,
+};
+use std::path::{Path, PathBuf};
+
+// コマンドライン引数
+struct Args {
+    // 解析するファイルのパス (省略時は対話的に入力する)
+    path: Option<String>,
+    // 結果をJSONで出力する
+    json: bool,
+    // 追加のコード定義ファイルのパス
+    chords: Option<String>,
+    // 検出したピークの情報も出力する
+    verbose: bool,
+    // 解析する範囲の開始時刻 (秒)
+    start: Option<f64>,
+    // 解析する範囲の長さ (秒)
+    duration: Option<f64>,
+    // 窓関数の種類
+    window: Option<WindowKind>,
 }
 
-
-fn get_note(freq: f64) -> String {
-    let notes = vec![
-        "A", "A#", "B", "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#"
-    ];
-
-    let octave = (4f64 + (freq / 440.0).log2()).round() as i32;
-    let note = notes[(((4f64 + (freq / 440.0).log2()) * 12f64).round() as usize) % 12];
-    format!("{}{}", note, octave)
+const USAGE: &str = "使い方: signal [--json] [--verbose] [--chords コード定義.json] [--start 秒] [--duration 秒] [--window 窓関数] [ファイル名またはディレクトリ]
+  窓関数: hamming (既定), hann, blackman, blackman-harris, rectangular";
+
+// 窓関数の名前をWindowKindに変換する
+fn parse_window(name: &str) -> Option<WindowKind> {
+    match name {
+        "hamming" => Some(WindowKind::Hamming),
+        "hann" => Some(WindowKind::Hann),
+        "blackman" => Some(WindowKind::Blackman),
+        "blackman-harris" => Some(WindowKind::BlackmanHarris),
+        "rectangular" => Some(WindowKind::Rectangular),
+        _ => None,
+    }
 }
 
-// ピークからコードを解析
-fn analyze_chord(peaks: Vec<f64>) -> String {
-    // ルート音の周波数を取得
-    let root_freq = peaks.iter().fold(0.0/0.0, |m, v| v.min(m));
-
-    // ルート音より1オクターブ高い音を除去
-    let peaks: Vec<f64> = peaks.iter().filter(|x| **x < root_freq * 2.0).map(|x| *x).collect();
-
-    // ルート音からの相対音程を取得
-    let mut distances: Vec<i32> = peaks.iter().map(|x| ((x / root_freq).log2() * 12f64).round() as i32).collect();
-
-    distances.sort();
-    distances.dedup();
-
-    // ルート音からの相対音程とコード名のハッシュマップ
-    let chord_map: HashMap<Vec<i32>, String> =  {
-        let mut chord_map: HashMap<Vec<i32>, String> =  HashMap::new();
-        chord_map.insert(vec![0, 4, 7], "major".to_string());
-        chord_map.insert(vec![0, 3, 7], "minor".to_string());
-        chord_map.insert(vec![0, 4, 7, 10], "seventh".to_string());
-        chord_map.insert(vec![0, 4, 7, 11], "major_seventh".to_string());
-        chord_map.insert(vec![0, 3, 7, 10], "minor_seventh".to_string());
-        chord_map.insert(vec![0, 3, 7, 11], "minor_major_seventh".to_string());
-        chord_map.insert(vec![0, 4, 8], "augmented".to_string());
-        chord_map.insert(vec![0, 3, 6], "diminished".to_string());
-        chord_map.insert(vec![0, 3, 6, 9], "diminished_seventh".to_string());
-        chord_map.insert(vec![0, 3, 6, 10], "minor_seventh_flat_five".to_string());
-
-        chord_map
-    };
+// コマンドライン引数を解析する
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args { path: None, json: false, chords: None, verbose: false, start: None, duration: None, window: None };
+
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => {
+                args.json = true;
+                continue;
+            },
+            "--verbose" | "-v" => {
+                args.verbose = true;
+                continue;
+            },
+            "--chords" => {
+                args.chords = Some(iter.next().ok_or("--chordsにはファイルを指定してください")?);
+                continue;
+            },
+            "--start" | "--duration" => {
+                let seconds: f64 = iter.next()
+                    .and_then(|value| value.parse().ok())
+                    .ok_or(format!("{}には秒数を指定してください", arg))?;
+                if arg == "--start" {
+                    args.start = Some(seconds);
+                } else {
+                    args.duration = Some(seconds);
+                }
+                continue;
+            },
+            "--window" => {
+                let name = iter.next().ok_or("--windowには窓関数の名前を指定してください")?;
+                args.window = Some(parse_window(&name).ok_or(format!("不明な窓関数です: {}", name))?);
+                continue;
+            },
+            _ if arg.starts_with("--") => return Err(format!("不明なオプションです: {}", arg)),
+            _ => {},
+        }
 
-    // 音程からコード名を取得
-    let name = match chord_map.get(&distances) {
-        Some(name) => name.clone(),
-        // 未知のコードは全て単音として扱う
-        None => "".to_string(),
-    };
+        if args.path.is_some() {
+            return Err(format!("ファイルは1つだけ指定してください: {}", arg));
+        }
+        args.path = Some(arg);
+    }
 
-    // ルート音の音名とコード名を結合
-    format!("{} {}", get_note(root_freq), name)
+    Ok(args)
 }
 
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+// 標準入力からファイル名を受け取り、chordsフォルダ内のパスにする
+fn prompt_path() -> String {
     println!("ファイル名を入力してください: ");
     let mut input = String::new();
     std::io::stdin().read_line(&mut input).unwrap();
 
-    // 拡張子がない場合は補完する
-    let path = if input.trim().contains(".") {
+    // ディレクトリや拡張子付きの名前はそのまま、拡張子がない場合は補完する
+    if input.trim().contains(".") || Path::new("chords").join(input.trim()).is_dir() {
         format!("chords/{}", input.trim())
     } else {
         format!("chords/{}.wav", input.trim())
-    };
-
-    let Ok((spec, samples)) = get_wave(&path) else {
-        println!("ファイルが見つかりません");
-        return Ok(());
-    };
-
-    // FFTを実行
-    let mut planner = FftPlanner::<f64>::new();
-    let fft = planner.plan_fft_forward(samples.len());
+    }
+}
 
-    let mut input: Vec<Complex<f64>> = samples.iter().map(|&x| Complex { re: x, im: 0.0 }).collect();
+// 検出したピークの周波数、振幅、音名、平均律からのずれを1行ずつ出力する
+//
+// JSONで出力する場合は標準出力を汚さないように標準エラー出力に出す。
+fn print_peaks(detail: &AnalysisDetail, json: bool) {
+    for ((freq, magnitude), note) in detail.peaks.iter().zip(&detail.chord.notes) {
+        let line = format!("{:>9.2} Hz  振幅 {:>10.3}  {:<4} {:+6.1} cents", freq, magnitude, note.note, note.cents);
+        if json {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
 
-    fft.process(&mut input);
+// エラーを出力して終了する
+//
+// JSONで出力する場合は、標準出力を読むプログラムが解析できるようにエラーもJSONで標準出力に出す。
+fn exit_with_error(message: &str, json: bool, code: i32) -> ! {
+    if json {
+        println!("{}", serde_json::json!({ "error": message }));
+    } else {
+        eprintln!("{}", message);
+    }
+    std::process::exit(code);
+}
 
-    let mut output: Vec<f64> = input.iter().map(|x| x.norm()).collect();
+// ディレクトリ内の音声ファイルを全て解析し、ファイル名とコードの表を出力する
+//
+// 解析に失敗したファイルの数を返す。
+fn analyze_dir(dir: &Path, options: &AnalyzeOptions, json: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| is_supported_extension(&ext.to_string_lossy())))
+        .collect();
+    paths.sort();
+
+    // 1つのファイルで失敗しても残りのファイルの解析は続ける
+    let results: Vec<(String, Result<ChordResult, String>)> = paths.iter().map(|path| {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let result = analyze_file_detailed(&path.to_string_lossy(), options).map(|detail| {
+            for warning in &detail.warnings {
+                eprintln!("{}: 警告: {}", name, warning);
+            }
+            detail.chord
+        }).map_err(|e| e.to_string());
+        (name, result)
+    }).collect();
+    let failures = results.iter().filter(|(_, result)| result.is_err()).count();
 
-    // 出力物の範囲の右側を削除
-    output.truncate(output.len() / 2);
+    if json {
+        let entries: Vec<serde_json::Value> = results.iter().map(|(name, result)| match result {
+            Ok(chord) => serde_json::json!({ "file": name, "chord": chord }),
+            Err(message) => serde_json::json!({ "file": name, "error": message }),
+        }).collect();
+        println!("{}", serde_json::to_string(&entries)?);
+        return Ok(failures);
+    }
+
+    let width = results.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+    for (name, result) in &results {
+        match result {
+            Ok(chord) => println!("{:<width$}  {} ({:.2})", name, chord, chord.confidence),
+            Err(message) => println!("{:<width$}  エラー: {}", name, message),
+        }
+    }
 
-    // 高周波ノイズを除去
-    output.truncate(20000);
+    Ok(failures)
+}
 
-    // 波形を平滑化
-    output = output.iter().zip(output.iter().skip(1)).zip(output.iter().skip(2)).map(|((x, y), z)| {
-        (x + y + z) / 3.0
-    }).collect();
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        },
+    };
 
-    // ピークを取得
-    let mut peaks: Vec<(usize, f64)> = output.iter().enumerate().zip(output.iter().skip(1)).zip(output.iter().skip(2)).filter_map(|(((i, x), y), z)| {
-        // 一個手前と一個後ろの値より大きい場合にピークとして取得
-        if y > x && y > z {
-            Some((i, *y))
-        } else {
-            None
+    let mut options = AnalyzeOptions::default();
+    if let Some(chords) = &args.chords {
+        options.dictionary = match ChordDictionary::load(chords) {
+            Ok(dictionary) => dictionary,
+            Err(error) => exit_with_error(&error.to_string(), args.json, 2),
+        };
+    }
+
+    if let Some(start) = args.start {
+        options.start_sec = start;
+    }
+    options.duration_sec = args.duration;
+    if let Some(window) = args.window {
+        options.window = window;
+    }
+
+    let path = args.path.unwrap_or_else(prompt_path);
+
+    // ディレクトリの場合は中のファイルをまとめて解析し、失敗したファイルがあれば0以外で終了する
+    if Path::new(&path).is_dir() {
+        match analyze_dir(Path::new(&path), &options, args.json) {
+            Ok(0) => return Ok(()),
+            Ok(_) => std::process::exit(1),
+            Err(error) => exit_with_error(&error.to_string(), args.json, 1),
         }
-    }).collect();
+    }
 
-    // ピークの中から上位10個を取得
-    peaks.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-    peaks.truncate(8);
+    let result = analyze_file_detailed(&path, &options).map(|detail| {
+        for warning in &detail.warnings {
+            eprintln!("警告: {}", warning);
+        }
+        if args.verbose {
+            print_peaks(&detail, args.json);
+        }
+        detail.chord
+    });
 
-    let main_freq: Vec<f64> = peaks.iter().map(|x| x.0 as f64 / input.len() as f64 * spec.sample_rate as f64).collect();
+    let chord = match result {
+        Ok(chord) => chord,
+        Err(error) => exit_with_error(&error.to_string(), args.json, 1),
+    };
 
-    println!("この音源のコードは {} です", analyze_chord(main_freq));
+    if args.json {
+        println!("{}", serde_json::to_string(&chord)?);
+    } else {
+        println!("この音源のコードは {} です (確からしさ {:.2})", chord, chord.confidence);
+    }
     Ok(())
 }
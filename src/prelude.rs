@@ -0,0 +1,13 @@
+// no_stdでも使う型とマクロ
+//
+// stdの有無に関わらず同じように書けるように、各モジュールでglobで読み込む。
+pub(crate) use alloc::format;
+pub(crate) use alloc::string::{String, ToString};
+pub(crate) use alloc::vec;
+pub(crate) use alloc::vec::Vec;
+// stdがない場合は浮動小数点数の関数をlibmで計算する
+//
+// 依存クレートがstdを読み込んでいるとstdのメソッドが優先され、使われないことがある。
+#[cfg(not(any(feature = "std", test)))]
+#[allow(unused_imports)]
+pub(crate) use num_traits::Float;
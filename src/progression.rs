@@ -0,0 +1,163 @@
+use crate::ChordResult;
+use crate::prelude::*;
+#[cfg(feature = "fft")]
+use crate::{AnalyzeOptions, analyze_samples, apply_window};
+use serde::{Deserialize, Serialize};
+
+// 同じコードが続いた区間
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChordSpan {
+    // 区間の開始時刻 (秒)
+    pub start_sec: f64,
+    // 区間の終了時刻 (秒)
+    pub end_sec: f64,
+    pub chord: ChordResult,
+}
+
+// フレームの長さと重なりの割合 (0.5なら50%、0.75なら75%) からずらす幅を求める
+pub fn overlap_hop_size(frame_size: usize, overlap: f64) -> usize {
+    ((frame_size as f64 * (1.0 - overlap.clamp(0.0, 1.0))).round() as usize).max(1)
+}
+
+// 信号をframe_sizeサンプルずつ、hop_sizeサンプルずらしながら解析する
+//
+// samplesは窓関数を適用していない信号で、各フレームにoptions.windowの窓関数を適用してから解析する。
+// 窓関数でフレームの両端が弱まるため、hop_sizeをframe_sizeの半分から4分の1にして
+// フレームを重ねると信号のどの部分も解析に使われる。
+// 周波数の分解能はframe_sizeで決まり、コードの切り替わりの時間分解能はhop_size / sample_rate秒になる。
+//
+// 各フレームの開始時刻 (秒) とコードを時刻順に返す。
+// 無音のフレームと、末尾のframe_sizeに満たない部分は解析しない。
+#[cfg(feature = "fft")]
+pub fn analyze_progression(
+    samples: &[f64],
+    sample_rate: u32,
+    frame_size: usize,
+    hop_size: usize,
+    options: &AnalyzeOptions,
+) -> Vec<(f64, ChordResult)> {
+    if frame_size == 0 || samples.len() < frame_size {
+        return Vec::new();
+    }
+
+    (0..=samples.len() - frame_size)
+        .step_by(hop_size.max(1))
+        .filter_map(|start| {
+            let frame = apply_window(&samples[start..start + frame_size], options.window);
+            let chord = analyze_samples(&frame, sample_rate, options).ok()?;
+            Some((start as f64 / sample_rate as f64, chord))
+        })
+        .collect()
+}
+
+// 同じコードが続くフレームを1つの区間にまとめる
+//
+// ルート音、コードの種類、最低音が同じものを同じコードとみなす。
+// 区間は最後のフレームの開始からframe_secまで続き、次の区間が始まるとそこで終わる。
+pub fn merge_progression(frames: Vec<(f64, ChordResult)>, frame_sec: f64) -> Vec<ChordSpan> {
+    let mut spans: Vec<ChordSpan> = Vec::new();
+
+    for (time, chord) in frames {
+        if let Some(last) = spans.last_mut() {
+            if last.chord.root_note == chord.root_note
+                && last.chord.quality == chord.quality
+                && last.chord.bass_note == chord.bass_note
+            {
+                last.end_sec = time + frame_sec;
+                continue;
+            }
+            last.end_sec = last.end_sec.min(time);
+        }
+
+        spans.push(ChordSpan {
+            start_sec: time,
+            end_sec: time + frame_sec,
+            chord,
+        });
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{STANDARD_TUNING_HZ, analyze_chord, midi_to_freq};
+
+    // ルート音のMIDIノート番号と音程から解析したコード
+    fn chord(root_midi: i32, intervals: &[i32]) -> ChordResult {
+        analyze_chord(intervals.iter().map(|x| midi_to_freq(root_midi + x, STANDARD_TUNING_HZ)).collect()).unwrap()
+    }
+
+    #[test]
+    fn overlap_hop_size_edge_cases() {
+        assert_eq!(overlap_hop_size(4096, 0.0), 4096);
+        assert_eq!(overlap_hop_size(4096, 0.5), 2048);
+        assert_eq!(overlap_hop_size(4096, 0.75), 1024);
+        // 100%の重なりでも少なくとも1サンプルはずらす
+        assert_eq!(overlap_hop_size(4096, 1.0), 1);
+        // 範囲外の割合は0.0〜1.0に切り詰める
+        assert_eq!(overlap_hop_size(4096, -0.5), 4096);
+        assert_eq!(overlap_hop_size(4096, 1.5), 1);
+        assert_eq!(overlap_hop_size(0, 0.5), 1);
+    }
+
+    #[test]
+    fn merges_consecutive_frames_with_same_chord() {
+        let c = chord(60, &[0, 4, 7]);
+        let am = chord(57, &[0, 3, 7]);
+        let frames = vec![(0.0, c.clone()), (0.5, c.clone()), (1.0, am.clone()), (1.5, am.clone()), (2.0, c.clone())];
+
+        let spans = merge_progression(frames, 1.0);
+
+        let summary: Vec<(f64, f64, String)> = spans.iter().map(|x| (x.start_sec, x.end_sec, x.chord.to_string())).collect();
+        assert_eq!(summary, [
+            (0.0, 1.0, c.to_string()),
+            (1.0, 2.0, am.to_string()),
+            (2.0, 3.0, c.to_string()),
+        ]);
+    }
+
+    #[test]
+    fn different_bass_starts_new_span() {
+        let root_position = chord(60, &[0, 4, 7]);
+        let inversion = chord(52, &[0, 3, 8]);
+        assert_eq!(root_position.quality, inversion.quality);
+
+        let spans = merge_progression(vec![(0.0, root_position), (0.5, inversion)], 0.5);
+        assert_eq!(spans.len(), 2);
+        assert!(merge_progression(Vec::new(), 0.5).is_empty());
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn transition_is_found_near_boundary() {
+        let sample_rate = 22050;
+        let second = sample_rate as usize;
+        // 1秒の時点でCメジャーからAマイナーに切り替わる
+        let mut samples = crate::test_util::sine(&[261.63, 329.63, 392.0], sample_rate, second);
+        samples.extend(crate::test_util::sine(&[220.0, 261.63, 329.63], sample_rate, second));
+
+        let frame_size = 4096;
+        let frame_sec = frame_size as f64 / sample_rate as f64;
+        let hop_size = overlap_hop_size(frame_size, 0.75);
+        let frames = analyze_progression(&samples, sample_rate, frame_size, hop_size, &AnalyzeOptions::default());
+
+        // 境界をまたがないフレームはどちらかのコードになる
+        for (time, chord) in &frames {
+            if time + frame_sec <= 1.0 {
+                assert_eq!(chord.to_string(), "C4 major", "{}秒", time);
+            } else if *time >= 1.0 {
+                assert_eq!(chord.to_string(), "A3 minor", "{}秒", time);
+            }
+        }
+
+        // 両方のコードが混ざる区間は境界の手前のフレーム1つ分の中に収まる
+        let spans = merge_progression(frames, frame_sec);
+        assert_eq!(spans.first().unwrap().chord.to_string(), "C4 major");
+        assert_eq!(spans.last().unwrap().chord.to_string(), "A3 minor");
+        for span in &spans[1..] {
+            assert!(span.start_sec > 1.0 - frame_sec && span.start_sec <= 1.0, "{}秒", span.start_sec);
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{AnalysisDetail, AnalyzeError, AnalyzeOptions, ChordResult, analyze_spectrum, fft_len};
+use realfft::{RealFftPlanner, RealToComplex};
+use std::cell::RefCell;
+use std::sync::Arc;
+
+thread_local! {
+    // FFTのプランは長さごとにプランナーの中でキャッシュされる
+    static PLANNER: RefCell<RealFftPlanner<f64>> = RefCell::new(RealFftPlanner::new());
+}
+
+// 長さlenの実数入力FFTのプランを返す (同じ長さのプランは再利用する)
+pub(crate) fn plan_fft(len: usize) -> Arc<dyn RealToComplex<f64>> {
+    PLANNER.with(|planner| planner.borrow_mut().plan_fft_forward(len))
+}
+
+// FFTを実行し、振幅スペクトルを返す
+pub fn spectrum(samples: &[f64]) -> Vec<f64> {
+    // 実数入力のFFTで必要な半分のスペクトルだけを計算する
+    let fft = plan_fft(fft_len(samples.len()));
+
+    spectrum_with(samples, &*fft)
+}
+
+// 作成済みのFFTのプランで振幅スペクトルを計算する
+pub(crate) fn spectrum_with(samples: &[f64], fft: &dyn RealToComplex<f64>) -> Vec<f64> {
+    let len = fft.len();
+
+    // 末尾をゼロで埋めてFFTの長さに揃える
+    let mut input = samples.to_vec();
+    input.resize(len, 0.0);
+    let mut result = fft.make_output_vec();
+
+    // 入出力の長さはプランから作っているので失敗しない
+    fft.process(&mut input, &mut result).unwrap();
+
+    let mut output: Vec<f64> = result.iter().map(|x| x.norm()).collect();
+
+    // 複素FFTの前半と同じ範囲に揃える
+    output.truncate(len / 2);
+
+    output
+}
+
+// これより小さい振幅しかない信号は無音とみなす (約-80dB)
+pub(crate) const SILENCE_THRESHOLD: f64 = 1e-4;
+
+// 窓関数を適用済みの信号を解析してコードを返す
+//
+// 信号が空か無音の場合はEmptySignalを返す。その他のエラーはanalyze_detailedと同じ。
+pub fn analyze_samples(samples: &[f64], sample_rate: u32, options: &AnalyzeOptions) -> Result<ChordResult, AnalyzeError> {
+    analyze_detailed(samples, sample_rate, options).map(|detail| detail.chord)
+}
+
+// 窓関数を適用済みの信号を解析し、スペクトルやピークも含めた解析結果を返す
+//
+// 信号が空か無音の場合はEmptySignal、NaNや無限大のサンプルが含まれる場合はInvalidInput、
+// サンプリング周波数が0の場合はSampleRateTooLowを返す。
+pub fn analyze_detailed(samples: &[f64], sample_rate: u32, options: &AnalyzeOptions) -> Result<AnalysisDetail, AnalyzeError> {
+    if sample_rate == 0 {
+        return Err(AnalyzeError::SampleRateTooLow(0));
+    }
+    if let Some(i) = samples.iter().position(|x| !x.is_finite()) {
+        return Err(AnalyzeError::InvalidInput(format!("{}番目のサンプルが有限な値ではありません", i)));
+    }
+    if samples.iter().all(|x| x.abs() < SILENCE_THRESHOLD) {
+        return Err(AnalyzeError::EmptySignal);
+    }
+
+    analyze_spectrum(spectrum(samples), sample_rate, fft_len(samples.len()), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::sine;
+    use rustfft::FftPlanner;
+    use rustfft::num_complex::Complex;
+
+    // 虚部を0にした複素FFTで振幅スペクトルの前半を計算する
+    fn complex_spectrum(samples: &[f64]) -> Vec<f64> {
+        let len = fft_len(samples.len());
+        let mut buffer: Vec<Complex<f64>> = samples.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        buffer.resize(len, Complex::new(0.0, 0.0));
+        FftPlanner::new().plan_fft_forward(len).process(&mut buffer);
+
+        buffer[..len / 2].iter().map(|x| x.norm()).collect()
+    }
+
+    #[test]
+    fn real_fft_matches_complex_fft() {
+        let samples = sine(&[261.63, 329.63, 392.0], 44100, 3000);
+
+        let real = spectrum(&samples);
+        let complex = complex_spectrum(&samples);
+
+        assert_eq!(real.len(), complex.len());
+        for (a, b) in real.iter().zip(&complex) {
+            assert!((a - b).abs() < 1e-9, "{} {}", a, b);
+        }
+    }
+
+    #[test]
+    fn plans_are_reused_across_lengths() {
+        let lengths = [1000, 3000, 1000, 9000, 3000];
+        let plans: Vec<Arc<dyn RealToComplex<f64>>> = lengths.iter().map(|&len| plan_fft(fft_len(len))).collect();
+
+        // 間に別の長さを挟んでも、同じ長さには同じプランを返す
+        assert!(Arc::ptr_eq(&plans[0], &plans[2]));
+        assert!(Arc::ptr_eq(&plans[1], &plans[4]));
+        assert!(!Arc::ptr_eq(&plans[0], &plans[1]));
+
+        // 再利用したプランでも毎回同じスペクトルになる
+        for len in lengths {
+            let samples = sine(&[440.0], 44100, len);
+            let complex = complex_spectrum(&samples);
+            assert!(spectrum(&samples).iter().zip(&complex).all(|(a, b)| (a - b).abs() < 1e-9));
+        }
+    }
+
+    #[test]
+    fn odd_length_buffer_is_padded_and_keeps_peak_frequency() {
+        assert_eq!(fft_len(44099), 65536);
+
+        let samples = crate::apply_window(&sine(&[440.0], 44100, 44099), crate::WindowKind::Hann);
+        let detail = analyze_detailed(&samples, 44100, &AnalyzeOptions::default()).unwrap();
+
+        let (freq, _) = detail.peaks[0];
+        assert!((freq - 440.0).abs() < 0.1, "{}Hz", freq);
+        assert_eq!(detail.chord.notes[0].note, "A4");
+    }
+
+    #[test]
+    fn empty_and_silent_input_is_an_error() {
+        let options = AnalyzeOptions::default();
+
+        assert!(matches!(analyze_samples(&[], 44100, &options), Err(AnalyzeError::EmptySignal)));
+        assert!(matches!(analyze_samples(&[0.0; 4096], 44100, &options), Err(AnalyzeError::EmptySignal)));
+        assert!(spectrum(&[]).is_empty());
+    }
+
+    #[test]
+    fn invalid_samples_and_zero_rate_have_their_own_errors() {
+        let options = AnalyzeOptions::default();
+        let mut samples = sine(&[440.0], 44100, 4096);
+
+        assert!(matches!(analyze_samples(&samples, 0, &options), Err(AnalyzeError::SampleRateTooLow(0))));
+
+        samples[100] = f64::NAN;
+        assert!(matches!(analyze_samples(&samples, 44100, &options), Err(AnalyzeError::InvalidInput(_))));
+        samples[100] = f64::INFINITY;
+        assert!(matches!(analyze_samples(&samples, 44100, &options), Err(AnalyzeError::InvalidInput(_))));
+        assert!(matches!(analyze_samples(&[f64::NAN; 16], 44100, &options), Err(AnalyzeError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn pure_440_hz_tone_is_a4() {
+        let samples = crate::apply_window(&sine(&[440.0], 44100, 8192), crate::WindowKind::Hamming);
+        let result = analyze_samples(&samples, 44100, &AnalyzeOptions::default()).unwrap();
+
+        assert_eq!(result.notes.len(), 1);
+        assert_eq!(result.notes[0].note, "A4");
+        assert!(result.notes[0].cents.abs() < 5.0);
+    }
+
+    #[test]
+    fn extra_tones_lower_confidence() {
+        let options = AnalyzeOptions::default();
+        let analyze = |freqs: &[f64]| {
+            let samples = crate::apply_window(&sine(freqs, 44100, 16384), crate::WindowKind::Hamming);
+            analyze_samples(&samples, 44100, &options).unwrap()
+        };
+
+        let clean = analyze(&[261.63, 329.63, 392.0]);
+        let noisy = analyze(&[261.63, 329.63, 392.0, 370.0]);
+
+        assert_eq!(clean.quality, "major");
+        assert!(clean.confidence > 0.9, "{}", clean.confidence);
+        assert!(noisy.confidence < clean.confidence, "{} {}", noisy.confidence, clean.confidence);
+    }
+
+    #[test]
+    fn faint_extra_tone_still_resolves_to_triad() {
+        let mut samples = sine(&[261.63, 329.63, 392.0], 44100, 16384);
+        // 三和音の各音の約7.5%の振幅のF#4を加える
+        for (x, y) in samples.iter_mut().zip(sine(&[369.99], 44100, 16384)) {
+            *x += 0.025 * y;
+        }
+        let samples = crate::apply_window(&samples, crate::WindowKind::Hamming);
+
+        let detail = analyze_detailed(&samples, 44100, &AnalyzeOptions::default()).unwrap();
+        assert_eq!(detail.peaks.len(), 4);
+        assert_eq!(detail.chord.to_string(), "C4 major");
+    }
+
+    #[test]
+    fn detailed_result_includes_spectrum_and_selected_peaks() {
+        let options = AnalyzeOptions::default();
+        let samples = crate::apply_window(&sine(&[261.63, 329.63, 392.0], 44100, 16384), crate::WindowKind::Hamming);
+        let detail = analyze_detailed(&samples, 44100, &options).unwrap();
+
+        // 各ビンの周波数が付き、上限の周波数より上は切り詰められている
+        assert_eq!(detail.spectrum.len(), detail.frequencies.len());
+        assert_eq!(detail.frequencies[1], crate::bin_to_freq(1.0, fft_len(samples.len()), 44100));
+        assert!(detail.frequencies.iter().all(|&freq| freq < options.max_frequency_hz));
+
+        // ピークはコードの判定に使った周波数と、その周波数の振幅
+        assert_eq!(detail.peaks.iter().map(|x| x.0).collect::<Vec<f64>>(), detail.chord.peaks);
+        assert!(detail.peaks.iter().all(|x| x.1 > 0.0));
+        assert_eq!(detail.chord, analyze_samples(&samples, 44100, &options).unwrap());
+    }
+}
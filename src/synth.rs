@@ -0,0 +1,123 @@
+#[cfg(feature = "io")]
+use crate::AnalyzeError;
+use crate::prelude::*;
+use crate::{ChordResult, STANDARD_TUNING_HZ, midi_to_freq};
+
+// 音の始めと終わりのフェードの長さ (秒)。プツッというノイズを防ぐ
+const FADE_SEC: f64 = 0.01;
+
+// コードの解析結果を正弦波の和で鳴らした信号を返す
+//
+// 検出したピークの周波数を鳴らし、ピークがない場合 (クロマから求めた場合など) は
+// ルート音と構成音の音程から平均律の周波数を求める。信号の最大振幅は0.8程度になる。
+pub fn synthesize_chord(result: &ChordResult, duration: f64, sample_rate: u32) -> Vec<f64> {
+    let freqs: Vec<f64> = if result.peaks.is_empty() {
+        result.intervals.iter().map(|x| midi_to_freq(result.root_midi + x, STANDARD_TUNING_HZ)).collect()
+    } else {
+        result.peaks.clone()
+    };
+
+    let len = (duration.max(0.0) * sample_rate as f64).round() as usize;
+    if freqs.is_empty() {
+        return vec![0.0; len];
+    }
+
+    let amplitude = 0.8 / freqs.len() as f64;
+    let fade = (FADE_SEC * sample_rate as f64).max(1.0);
+    (0..len)
+        .map(|i| {
+            let t = i as f64 / sample_rate as f64;
+            let envelope = (i as f64 / fade).min((len - i) as f64 / fade).min(1.0);
+            let x: f64 = freqs.iter().map(|f| (2.0 * core::f64::consts::PI * f * t).sin()).sum();
+            x * amplitude * envelope
+        })
+        .collect()
+}
+
+// モノラルの信号を16bitのWavファイルに書き出す (-1.0〜1.0の範囲外は切り詰める)
+#[cfg(feature = "io")]
+pub fn write_wave(path: &str, samples: &[f64], sample_rate: u32) -> Result<(), AnalyzeError> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut writer = hound::WavWriter::create(path, spec).map_err(|e| AnalyzeError::from_hound(e, path))?;
+    for x in samples {
+        let sample = (x.clamp(-1.0, 1.0) * i16::MAX as f64).round() as i16;
+        writer.write_sample(sample).map_err(|e| AnalyzeError::from_hound(e, path))?;
+    }
+    writer.finalize().map_err(|e| AnalyzeError::from_hound(e, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChordDictionary, analyze_chord, analyze_chord_from_chroma};
+
+    // C4 E4 G4のピークから解析したCメジャー
+    fn c_major() -> ChordResult {
+        analyze_chord([60, 64, 67].iter().map(|&x| midi_to_freq(x, STANDARD_TUNING_HZ)).collect()).unwrap()
+    }
+
+    #[test]
+    fn synthesized_signal_has_requested_length_and_level() {
+        let samples = synthesize_chord(&c_major(), 0.5, 8000);
+
+        assert_eq!(samples.len(), 4000);
+        assert!(samples.iter().all(|x| x.abs() <= 0.8 + 1e-9));
+        // フェードで始めと終わりは無音になる
+        assert_eq!(samples[0], 0.0);
+        assert!(samples[3999].abs() < 0.01);
+    }
+
+    #[test]
+    fn chord_without_peaks_uses_intervals() {
+        let mut chroma = [0.0; 12];
+        for x in [0, 4, 7] {
+            chroma[x] = 1.0;
+        }
+        let (result, _) = analyze_chord_from_chroma(&chroma, &ChordDictionary::default()).unwrap();
+        assert!(result.peaks.is_empty());
+
+        let samples = synthesize_chord(&result, 0.1, 8000);
+        assert!(samples.iter().any(|x| x.abs() > 0.1));
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn synthesized_chord_analyzes_to_same_chord() {
+        let chord = c_major();
+        let samples = crate::apply_window(&synthesize_chord(&chord, 0.5, 44100), crate::WindowKind::Hamming);
+
+        let result = crate::analyze_samples(&samples, 44100, &crate::AnalyzeOptions::default()).unwrap();
+        assert_eq!(result.to_string(), chord.to_string());
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn written_wave_round_trips() {
+        let file = crate::test_util::TempFile::new("synth.wav");
+        let samples = synthesize_chord(&c_major(), 0.1, 22050);
+        write_wave(file.path(), &samples, 22050).unwrap();
+
+        let (spec, read) = crate::get_wave(file.path(), crate::WindowKind::Rectangular).unwrap();
+        assert_eq!((spec.channels, spec.sample_rate, spec.bits_per_sample), (1, 22050, 16));
+        assert_eq!(read.len(), samples.len());
+        // 16bitに量子化した誤差の範囲で一致する
+        assert!(read.iter().zip(&samples).all(|(a, b)| (a - b).abs() < 1.0 / 16384.0));
+    }
+
+    #[cfg(feature = "io")]
+    #[test]
+    fn out_of_range_samples_are_clipped() {
+        let file = crate::test_util::TempFile::new("clipped.wav");
+        write_wave(file.path(), &[2.0, -2.0], 8000).unwrap();
+
+        let (_, read) = crate::get_wave(file.path(), crate::WindowKind::Rectangular).unwrap();
+        assert!((read[0] - 1.0).abs() < 1e-4);
+        assert!((read[1] + 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,35 @@
+// テストで共通に使う補助関数
+
+// テスト用の一時ファイル (破棄すると削除する)
+#[cfg(feature = "io")]
+pub(crate) struct TempFile(String);
+
+#[cfg(feature = "io")]
+impl TempFile {
+    // 並列に実行するテスト同士で衝突しないように名前を付ける
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("signal-test-{}-{}", std::process::id(), name));
+        TempFile(path.to_string_lossy().to_string())
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "io")]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+// 周波数の正弦波を足し合わせた長さlenの信号を返す
+pub(crate) fn sine(freqs: &[f64], sample_rate: u32, len: usize) -> Vec<f64> {
+    (0..len)
+        .map(|i| {
+            let t = i as f64 / sample_rate as f64;
+            freqs.iter().map(|f| (2.0 * std::f64::consts::PI * f * t).sin()).sum::<f64>() / freqs.len() as f64
+        })
+        .collect()
+}
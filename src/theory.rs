@@ -0,0 +1,1059 @@
+// 音名の計算とコードの判定
+//
+// houndやFFT、ファイルの入出力に依存しないため、featureを全て無効にしたno_stdの環境でも使える。
+use crate::prelude::*;
+use crate::{AnalyzeError, ChordDictionary};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+// コードの解析結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChordResult {
+    // ルート音の音名
+    pub root_note: String,
+    // ルート音のオクターブ
+    pub octave: i32,
+    // ルート音のMIDIノート番号
+    pub root_midi: i32,
+    // コードの種類 (未知のコードは空文字)
+    pub quality: String,
+    // ルート音からの相対音程 (半音単位)
+    pub intervals: Vec<i32>,
+    // 転回形や分数コードの場合の最低音 (例: "E3")
+    pub bass_note: Option<String>,
+    // 転回形の番号 (0は基本形、1は第1転回形。最低音が構成音でない分数コードは0)
+    pub inversion: usize,
+    // 辞書の音程からずれていた音程の数
+    pub deviations: usize,
+    // 解析に使ったピークの周波数 (Hz)
+    pub peaks: Vec<f64>,
+    // 各ピークの音名と平均律からのずれ
+    pub notes: Vec<DetectedNote>,
+    // 判定したコードの確からしさ (0.0〜1.0)
+    pub confidence: f64,
+}
+
+// ピークの音名と平均律からのずれ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedNote {
+    // ピークの周波数 (Hz)
+    pub freq: f64,
+    // 最も近い音名 (例: "A4")
+    pub note: String,
+    // 最も近い音のMIDIノート番号
+    pub midi: i32,
+    // 最も近い音からのずれ (セント、高い場合は正)
+    pub cents: f64,
+}
+
+impl fmt::Display for DetectedNote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:+.0} cents", self.note, self.cents)
+    }
+}
+
+impl ChordResult {
+    // ルート音と最低音の音名を指定した表記に書き換える
+    pub fn with_accidentals(mut self, style: AccidentalStyle) -> Self {
+        self.root_note = style.respell(&self.root_note);
+        self.bass_note = self.bass_note.map(|note| style.respell(&note));
+        self
+    }
+}
+
+impl fmt::Display for ChordResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.root_note, self.octave)?;
+        if !self.quality.is_empty() {
+            write!(f, " {}", self.quality)?;
+        }
+        if let Some(bass_note) = &self.bass_note {
+            write!(f, " / {} bass", bass_note)?;
+        }
+        Ok(())
+    }
+}
+
+// Aから始まる音名の配列
+pub(crate) const NOTES: [&str; 12] = [
+    "A", "A#", "B", "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#"
+];
+
+// フラットで表記した音名の配列
+const FLAT_NOTES: [&str; 12] = [
+    "A", "Bb", "B", "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab"
+];
+
+// 派生音をシャープとフラットのどちらで表記するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AccidentalStyle {
+    #[default]
+    Sharp,
+    Flat,
+}
+
+impl AccidentalStyle {
+    // 調に合った表記を返す
+    //
+    // keyは"Eb"や"F#"のような主音の音名で、短調の場合は"Cm"のように末尾にmを付ける。
+    pub fn for_key(key: &str) -> AccidentalStyle {
+        let (tonic, minor) = match key.strip_suffix('m') {
+            Some(tonic) => (tonic, true),
+            None => (key, false),
+        };
+
+        if tonic.len() > 1 && tonic.ends_with('b') {
+            return AccidentalStyle::Flat;
+        }
+
+        match (tonic, minor) {
+            ("F", false) | ("D", true) | ("G", true) | ("C", true) | ("F", true) => AccidentalStyle::Flat,
+            _ => AccidentalStyle::Sharp,
+        }
+    }
+
+    // この表記での音名の配列
+    fn notes(&self) -> &'static [&'static str; 12] {
+        match self {
+            AccidentalStyle::Sharp => &NOTES,
+            AccidentalStyle::Flat => &FLAT_NOTES,
+        }
+    }
+
+    // 音名をこの表記に書き換える (例: "C#4"をフラットにすると"Db4")
+    fn respell(&self, note: &str) -> String {
+        let split = note.find(|c: char| c.is_ascii_digit() || c == '-').unwrap_or(note.len());
+        let (name, octave) = note.split_at(split);
+
+        let index = NOTES.iter().chain(FLAT_NOTES.iter()).position(|x| *x == name);
+        match index {
+            Some(index) => format!("{}{}", self.notes()[index % 12], octave),
+            None => note.to_string(),
+        }
+    }
+}
+
+// 標準の基準音A4の周波数 (Hz)
+pub const STANDARD_TUNING_HZ: f64 = 440.0;
+
+// 周波数から音名とオクターブを取得 (tuning_hzは基準音A4の周波数)
+fn note_and_octave(freq: f64, style: AccidentalStyle, tuning_hz: f64) -> (String, i32) {
+    // A4からの半音数
+    let semitones = ((freq / tuning_hz).log2() * 12f64).round() as i32;
+
+    // 音名の配列はAから始まるが、オクターブはCで切り替わる (AはCから9半音上)
+    let note = style.notes()[semitones.rem_euclid(12) as usize];
+    let octave = 4 + (semitones + 9).div_euclid(12);
+    (note.to_string(), octave)
+}
+
+// 周波数から音名を取得 (例: "A4")
+//
+// tuning_hzは基準音A4の周波数で、通常はSTANDARD_TUNING_HZ (440Hz)。
+pub fn get_note(freq: f64, style: AccidentalStyle, tuning_hz: f64) -> String {
+    let (note, octave) = note_and_octave(freq, style, tuning_hz);
+    format!("{}{}", note, octave)
+}
+
+// 周波数をMIDIノート番号に変換する (A4が69)
+pub fn freq_to_midi(freq: f64, tuning_hz: f64) -> i32 {
+    69 + ((freq / tuning_hz).log2() * 12f64).round() as i32
+}
+
+// MIDIノート番号を平均律の周波数 (Hz) に変換する
+pub fn midi_to_freq(midi: i32, tuning_hz: f64) -> f64 {
+    tuning_hz * 2f64.powf((midi - 69) as f64 / 12.0)
+}
+
+// 周波数の最も近い平均律の音からのずれをセントで返す (高い場合は正)
+pub fn get_cents(freq: f64, tuning_hz: f64) -> f64 {
+    let semitones = (freq / tuning_hz).log2() * 12f64;
+    (semitones - semitones.round()) * 100.0
+}
+
+// 各ピークの音名と平均律からのずれを求める
+pub fn detect_notes(peaks: &[f64], style: AccidentalStyle, tuning_hz: f64) -> Vec<DetectedNote> {
+    peaks.iter().map(|&freq| DetectedNote {
+        freq,
+        note: get_note(freq, style, tuning_hz),
+        midi: freq_to_midi(freq, tuning_hz),
+        cents: get_cents(freq, tuning_hz),
+    }).collect()
+}
+
+// 検出した音程の扱い方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IntervalMode {
+    // 全ての音程を1オクターブ内に畳み込む
+    #[default]
+    SingleOctave,
+    // 1オクターブより上にしかない音は13や14のような音程のまま残す
+    //
+    // 短2度と♭9thや、6thと13thを区別できる。
+    // 辞書の12未満の音程は1オクターブ上の音にも一致し、12以上の音程はその音程の音にだけ一致する。
+    MultiOctave,
+}
+
+// 重み付きの音程の集合をrootの音が0になるように回転させる
+//
+// MultiOctaveの場合、ルート音より上の音は1オクターブ以上離れていてもそのままの音程にする。
+fn rotate_intervals(distances: &[(i32, f64)], root: i32, mode: IntervalMode) -> Vec<(i32, f64)> {
+    let mut rotated: Vec<(i32, f64)> = distances.iter().map(|&(x, weight)| match mode {
+        IntervalMode::MultiOctave if x >= root => (x - root, weight),
+        _ => ((x - root).rem_euclid(12), weight),
+    }).collect();
+    rotated.sort_by_key(|x| x.0);
+    rotated
+}
+
+// ピークの周波数から最低音の周波数と、最低音からの相対音程とその重みを取得
+fn peak_intervals(peaks: &[f64], weights: &[f64], mode: IntervalMode) -> (f64, Vec<(i32, f64)>) {
+    // 最低音の周波数を取得
+    let bass_freq = peaks.iter().fold(f64::NAN, |m, v| v.min(m));
+
+    // 最低音からの相対音程を取得
+    //
+    // 1オクターブより上の音も構成音として残すことで、ディミニッシュセブンスのような
+    // 対称なコードがどの音を最低音にしても同じ構成音の集合になる。
+    let mut distances: Vec<(i32, f64)> = peaks.iter().zip(weights)
+        .map(|(x, &weight)| (((x / bass_freq).log2() * 12f64).round() as i32, weight))
+        .collect();
+    distances.sort_by_key(|x| x.0);
+
+    // 同じ音名の音は1つにまとめ、重みは最も大きいものにする
+    // (SingleOctaveの場合は1オクターブ内に畳み込み、MultiOctaveの場合は最も低い音の音程を残す)
+    let mut merged: Vec<(i32, f64)> = Vec::new();
+    for (x, weight) in distances {
+        let x = match mode {
+            IntervalMode::SingleOctave => x % 12,
+            IntervalMode::MultiOctave => x,
+        };
+        match merged.iter_mut().find(|m| m.0 % 12 == x % 12) {
+            Some(m) => m.1 = m.1.max(weight),
+            None => merged.push((x, weight)),
+        }
+    }
+    merged.sort_by_key(|x| x.0);
+
+    (bass_freq, merged)
+}
+
+// 最低音とルート音の音程からChordResultを組み立てる
+fn chord_result(bass_freq: f64, root: i32, intervals: Vec<i32>, quality: String) -> ChordResult {
+    // ルート音は最低音から1オクターブ以内のものとする
+    let root_freq = bass_freq * 2f64.powf(root.rem_euclid(12) as f64 / 12.0);
+    let (root_note, octave) = note_and_octave(root_freq, AccidentalStyle::Sharp, STANDARD_TUNING_HZ);
+
+    // 転回形の場合は構成音の並びでの最低音の位置と最低音を記録する
+    let bass = (-root).rem_euclid(12);
+    let inversion = intervals.iter().position(|&x| x == bass).unwrap_or(0);
+    let bass_note = if root == 0 { None } else { Some(get_note(bass_freq, AccidentalStyle::Sharp, STANDARD_TUNING_HZ)) };
+
+    ChordResult {
+        root_note,
+        octave,
+        root_midi: freq_to_midi(root_freq, STANDARD_TUNING_HZ),
+        quality,
+        intervals,
+        bass_note,
+        inversion,
+        deviations: 0,
+        peaks: Vec::new(),
+        notes: Vec::new(),
+        confidence: 0.0,
+    }
+}
+
+// 辞書の音程tと検出した音程oの半音数の差
+//
+// 12未満の音程は1オクターブ上の音程とも同じとみなす。
+fn interval_distance(t: i32, o: i32) -> i32 {
+    if t >= 12 {
+        return (o - t).abs();
+    }
+    let d = (o - t).rem_euclid(12);
+    d.min(12 - d)
+}
+
+// 辞書の音程と重み付きの検出した音程を対応付け、一致した数、そのうちずれていた数、
+// 対応する音がなかった辞書の音程、一致度を返す
+//
+// 先に完全に一致する音程を対応付けてから、残りをtolerance半音以内の最も近い音程と対応付ける。
+// 一致度は一致した音の重みの和を、辞書の音程の数と一致しなかった音の重みの和で割った値。
+// ずれていた音は重みを半分にして数える。
+fn match_intervals(template: &[i32], observed: &[(i32, f64)], tolerance: i32) -> (usize, usize, Vec<i32>, f64) {
+    let mut used = vec![false; observed.len()];
+    let mut unmatched = Vec::new();
+    let mut matched = 0;
+    let mut matched_weight = 0.0;
+
+    for t in template {
+        match observed.iter().position(|o| interval_distance(*t, o.0) == 0) {
+            Some(i) => {
+                used[i] = true;
+                matched += 1;
+                matched_weight += observed[i].1;
+            },
+            None => unmatched.push(*t),
+        }
+    }
+
+    let mut deviations = 0;
+    let mut omitted = Vec::new();
+    for t in unmatched {
+        let nearest = observed.iter().enumerate()
+            .filter(|(i, _)| !used[*i])
+            .map(|(i, o)| (i, interval_distance(t, o.0)))
+            .filter(|(_, d)| *d <= tolerance)
+            .min_by_key(|(_, d)| *d);
+
+        if let Some((i, _)) = nearest {
+            used[i] = true;
+            matched += 1;
+            deviations += 1;
+            matched_weight += 0.5 * observed[i].1;
+        } else {
+            omitted.push(t);
+        }
+    }
+
+    let extra_weight: f64 = observed.iter().zip(&used).filter(|(_, used)| !**used).map(|(o, _)| o.1).sum();
+    let score = matched_weight / (template.len() as f64 + extra_weight);
+
+    (matched, deviations, omitted, score)
+}
+
+// コードとして採用する最低の一致度
+const MIN_MATCH_SCORE: f64 = 0.5;
+
+// 省略されていてもコードとみなす音程 (完全5度)
+//
+// 3度や7度、sus2やsus4の2度や4度はコードの種類を決めるため、省略されている場合は採用しない。
+const OMITTABLE_INTERVAL: i32 = 7;
+
+// 部分的な一致をコードとして採用してよいかどうか
+fn is_acceptable(omitted: &[i32], score: f64) -> bool {
+    score >= MIN_MATCH_SCORE && omitted.iter().all(|&x| x == OMITTABLE_INTERVAL)
+}
+
+// ピークから候補となるコードを一致度の高い順に返す
+//
+// 各音程はtolerance半音までのずれを許容し、ずれた音程は半分の一致として数える。
+// 一致度は一致した音程の数を辞書と検出した音程の和集合の大きさで割った値 (1.0なら完全一致)。
+// 辞書の各コードについて、最も一致度の高いルート音の候補を1つだけ返す。
+// ルート音以外に一致する音がないコードは候補に含めない。
+// 3度が足りないような、analyze_chordではコードとして採用しない部分的な一致も含める。
+pub fn analyze_chord_candidates(peaks: Vec<f64>, tolerance: i32, dictionary: &ChordDictionary) -> Vec<(ChordResult, f64)> {
+    analyze_chord_candidates_with_mode(peaks, tolerance, dictionary, IntervalMode::default())
+}
+
+// 音程の扱い方を指定して、ピークから候補となるコードを一致度の高い順に返す
+pub fn analyze_chord_candidates_with_mode(
+    peaks: Vec<f64>,
+    tolerance: i32,
+    dictionary: &ChordDictionary,
+    mode: IntervalMode,
+) -> Vec<(ChordResult, f64)> {
+    chord_candidates(&peaks, &vec![1.0; peaks.len()], tolerance, dictionary, mode)
+        .into_iter()
+        .map(|(result, score, _)| (result, score))
+        .collect()
+}
+
+// 重み付きのピークから候補となるコードを一致度の高い順に返す
+//
+// 3つ目の値はコードとして採用してよい一致かどうか。
+fn chord_candidates(
+    peaks: &[f64],
+    weights: &[f64],
+    tolerance: i32,
+    dictionary: &ChordDictionary,
+    mode: IntervalMode,
+) -> Vec<(ChordResult, f64, bool)> {
+    let (bass_freq, distances) = peak_intervals(peaks, weights, mode);
+
+    let mut candidates: Vec<(ChordResult, f64, bool)> = dictionary.iter().filter_map(|(template, quality)| {
+        // 基本形から順に、各構成音をルートとした転回形を試す
+        let (root, rotated, matched, deviations, omitted, score) = distances.iter().map(|&(root, _)| {
+            let rotated = rotate_intervals(&distances, root, mode);
+            let (matched, deviations, omitted, score) = match_intervals(template, &rotated, tolerance);
+            (root, rotated, matched, deviations, omitted, score)
+        }).reduce(|best, current| if best.5 >= current.5 { best } else { current })?;
+
+        if matched <= 1 {
+            return None;
+        }
+
+        let intervals = rotated.iter().map(|x| x.0).collect();
+        let mut result = chord_result(bass_freq, root, intervals, quality.clone());
+        result.deviations = deviations;
+        result.confidence = score;
+        result.notes = detect_notes(peaks, AccidentalStyle::Sharp, STANDARD_TUNING_HZ);
+        result.peaks = peaks.to_vec();
+        Some((result, score, is_acceptable(&omitted, score)))
+    }).collect();
+
+    // 一致度が同じ場合は基本形、コード名の順に並べる
+    // (オーギュメントやディミニッシュセブンスのような対称なコードは最低音をルートとする)
+    candidates.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then(a.0.bass_note.is_some().cmp(&b.0.bass_note.is_some()))
+            .then(a.0.quality.cmp(&b.0.quality))
+    });
+
+    candidates
+}
+
+// ピークから指定した辞書のコードを解析 (音程のずれはtolerance半音まで許容する)
+pub fn analyze_chord_with_dictionary(peaks: Vec<f64>, tolerance: i32, dictionary: &ChordDictionary) -> Result<ChordResult, AnalyzeError> {
+    analyze_chord_with_mode(peaks, tolerance, dictionary, IntervalMode::default())
+}
+
+// 音程の扱い方を指定して、ピークから指定した辞書のコードを解析
+pub fn analyze_chord_with_mode(
+    peaks: Vec<f64>,
+    tolerance: i32,
+    dictionary: &ChordDictionary,
+    mode: IntervalMode,
+) -> Result<ChordResult, AnalyzeError> {
+    let weights = vec![1.0; peaks.len()];
+    analyze_chord_weighted(peaks, &weights, tolerance, dictionary, mode)
+}
+
+// 重み付きのピークから指定した辞書のコードを解析
+//
+// weightsは各ピークの重み (0.0〜1.0) で、弱いピークほど小さくする。ピークと同じ数だけ必要で、
+// 数が違う場合はInvalidInputを返す。
+// 重みの小さい音は、辞書の音程に一致しなくても一致度をあまり下げず、一致しても一致度をあまり上げない。
+// 完全5度以外の構成音が足りない場合や一致度が低い場合はコードとみなさず、種類が空の単音として返す。
+// ピークが空の場合はEmptySignal、正の有限な値でない周波数が含まれる場合はInvalidInputを返す。
+pub fn analyze_chord_weighted(
+    peaks: Vec<f64>,
+    weights: &[f64],
+    tolerance: i32,
+    dictionary: &ChordDictionary,
+    mode: IntervalMode,
+) -> Result<ChordResult, AnalyzeError> {
+    validate_peaks(&peaks)?;
+    if weights.len() != peaks.len() {
+        return Err(AnalyzeError::InvalidInput(format!("重みの数 ({}) がピークの数 ({}) と一致しません", weights.len(), peaks.len())));
+    }
+
+    let (bass_freq, distances) = peak_intervals(&peaks, weights, mode);
+
+    let best = best_candidate(chord_candidates(&peaks, weights, tolerance, dictionary, mode));
+
+    // 最低音を含めて完全に一致しない場合は、最低音を除いた上部の音で分数コードとして解析する
+    if best.as_ref().is_none_or(|(_, score)| *score < 1.0) {
+        if let Some(mut slash) = analyze_slash_chord(&peaks, weights, bass_freq, tolerance, dictionary, mode) {
+            let score = best.as_ref().map_or(0.0, |(_, score)| *score);
+            if slash.1 > score {
+                slash.0.notes = detect_notes(&peaks, AccidentalStyle::Sharp, STANDARD_TUNING_HZ);
+                slash.0.peaks = peaks;
+                return Ok(slash.0);
+            }
+        }
+    }
+
+    Ok(match best {
+        Some((result, _)) => result,
+        // 候補がない場合は単音として扱う
+        None => ChordResult {
+            notes: detect_notes(&peaks, AccidentalStyle::Sharp, STANDARD_TUNING_HZ),
+            peaks,
+            ..chord_result(bass_freq, 0, distances.iter().map(|x| x.0).collect(), "".to_string())
+        },
+    })
+}
+
+// ピークが空でなく、全て正の有限な周波数であることを確かめる
+fn validate_peaks(peaks: &[f64]) -> Result<(), AnalyzeError> {
+    if peaks.is_empty() {
+        return Err(AnalyzeError::EmptySignal);
+    }
+
+    match peaks.iter().find(|x| !(x.is_finite() && **x > 0.0)) {
+        Some(freq) => Err(AnalyzeError::InvalidInput(format!("ピークの周波数は正の有限な値にしてください: {}", freq))),
+        None => Ok(()),
+    }
+}
+
+// 一致度の高い順に並んだ候補から、コードとして採用してよい最初の候補を返す
+fn best_candidate(candidates: Vec<(ChordResult, f64, bool)>) -> Option<(ChordResult, f64)> {
+    candidates.into_iter().find(|(_, _, acceptable)| *acceptable).map(|(result, score, _)| (result, score))
+}
+
+// 最低音と同じ音名のピークを除いた上部の音でコードを解析し、最低音を付けた分数コードを返す
+fn analyze_slash_chord(
+    peaks: &[f64],
+    weights: &[f64],
+    bass_freq: f64,
+    tolerance: i32,
+    dictionary: &ChordDictionary,
+    mode: IntervalMode,
+) -> Option<(ChordResult, f64)> {
+    let (upper, upper_weights): (Vec<f64>, Vec<f64>) = peaks.iter().copied().zip(weights.iter().copied()).filter(|(x, _)| {
+        ((x / bass_freq).log2() * 12f64).round() as i32 % 12 != 0
+    }).unzip();
+
+    // 上部の音だけでコードになる必要がある
+    if upper.len() < 3 {
+        return None;
+    }
+
+    let (mut result, score) = best_candidate(chord_candidates(&upper, &upper_weights, tolerance, dictionary, mode))?;
+    result.bass_note = Some(get_note(bass_freq, AccidentalStyle::Sharp, STANDARD_TUNING_HZ));
+    result.inversion = 0;
+    Some((result, score))
+}
+
+// ピークからコードを解析 (音程のずれはtolerance半音まで許容する)
+pub fn analyze_chord_with_tolerance(peaks: Vec<f64>, tolerance: i32) -> Result<ChordResult, AnalyzeError> {
+    analyze_chord_with_dictionary(peaks, tolerance, &ChordDictionary::default())
+}
+
+// ピークからコードを解析 (ピークが空の場合はEmptySignalを返す)
+pub fn analyze_chord(peaks: Vec<f64>) -> Result<ChordResult, AnalyzeError> {
+    analyze_chord_with_tolerance(peaks, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // ルート音のMIDIノート番号と音程から平均律のピークの周波数を作る
+    fn chord_peaks(root_midi: i32, intervals: &[i32]) -> Vec<f64> {
+        intervals.iter().map(|x| midi_to_freq(root_midi + x, STANDARD_TUNING_HZ)).collect()
+    }
+
+    #[test]
+    fn analyze_chord_names_a_minor_triad() {
+        // A3 C4 E4
+        assert_eq!(analyze_chord(vec![220.0, 261.63, 329.63]).unwrap().to_string(), "A3 minor");
+    }
+
+    #[test]
+    fn chord_result_has_root_quality_and_intervals() {
+        // 順番が入れ替わっていても最低音をルートにする
+        let result = analyze_chord(vec![329.63, 220.0, 261.63]).unwrap();
+
+        assert_eq!(result.root_note, "A");
+        assert_eq!(result.octave, 3);
+        assert_eq!(result.quality, "minor");
+        assert_eq!(result.intervals, [0, 3, 7]);
+    }
+
+    #[test]
+    fn get_note_switches_octave_at_c() {
+        let table = [
+            (246.94, "B3"),
+            (261.63, "C4"),
+            (277.18, "C#4"),
+            (293.66, "D4"),
+            (311.13, "D#4"),
+            (329.63, "E4"),
+            (349.23, "F4"),
+            (369.99, "F#4"),
+            (392.00, "G4"),
+            (415.30, "G#4"),
+            (440.00, "A4"),
+            (466.16, "A#4"),
+            (493.88, "B4"),
+            (523.25, "C5"),
+        ];
+
+        for (freq, note) in table {
+            assert_eq!(get_note(freq, AccidentalStyle::Sharp, STANDARD_TUNING_HZ), note, "{}Hz", freq);
+        }
+    }
+
+    #[test]
+    fn recognizes_suspended_chords() {
+        for (intervals, quality) in [
+            (&[0, 2, 7][..], "sus2"),
+            (&[0, 5, 7][..], "sus4"),
+            (&[0, 2, 7, 10][..], "seventh_sus2"),
+            (&[0, 5, 7, 10][..], "seventh_sus4"),
+        ] {
+            let result = analyze_chord(chord_peaks(60, intervals)).unwrap();
+            assert_eq!(result.quality, quality);
+            assert_eq!(result.root_note, "C");
+            assert_eq!(result.bass_note, None);
+        }
+    }
+
+    #[test]
+    fn recognizes_sixth_and_add9_chords() {
+        for (intervals, quality) in [
+            (&[0, 4, 7, 9][..], "sixth"),
+            (&[0, 3, 7, 9][..], "minor_sixth"),
+            // 9thを1オクターブ上に置いても畳み込んで判定する
+            (&[0, 4, 7, 14][..], "add9"),
+            (&[0, 3, 7, 14][..], "minor_add9"),
+        ] {
+            let result = analyze_chord(chord_peaks(60, intervals)).unwrap();
+            assert_eq!(result.quality, quality);
+            assert_eq!(result.root_note, "C");
+        }
+    }
+
+    #[test]
+    fn rejects_empty_peaks() {
+        assert!(matches!(analyze_chord(Vec::new()), Err(AnalyzeError::EmptySignal)));
+        assert!(analyze_chord_candidates(Vec::new(), 0, &ChordDictionary::default()).is_empty());
+    }
+
+    #[test]
+    fn rejects_non_positive_and_non_finite_peaks() {
+        for peaks in [vec![0.0], vec![-5.0, 100.0], vec![261.63, f64::NAN], vec![f64::INFINITY, 329.63]] {
+            assert!(matches!(analyze_chord(peaks.clone()), Err(AnalyzeError::InvalidInput(_))), "{:?}", peaks);
+        }
+    }
+
+    #[test]
+    fn bare_fifth_is_not_reported_as_a_triad() {
+        let result = analyze_chord(chord_peaks(60, &[0, 7])).unwrap();
+
+        assert_eq!(result.quality, "");
+        assert_eq!(result.root_note, "C");
+        assert_eq!(result.to_string(), "C4");
+    }
+
+    #[test]
+    fn chord_without_fifth_is_still_recognized() {
+        let result = analyze_chord(chord_peaks(60, &[0, 4, 10])).unwrap();
+        assert_eq!(result.quality, "seventh");
+        assert!(result.confidence < 1.0);
+    }
+
+    #[test]
+    fn candidates_are_ranked_by_score() {
+        let candidates = analyze_chord_candidates(chord_peaks(60, &[0, 4, 7]), 0, &ChordDictionary::default());
+
+        assert_eq!(candidates[0].0.quality, "major");
+        assert_eq!(candidates[0].1, 1.0);
+        assert!(candidates.windows(2).all(|x| x[0].1 >= x[1].1));
+
+        // 部分的な一致も候補として残る
+        let candidates = analyze_chord_candidates(chord_peaks(60, &[0, 7]), 0, &ChordDictionary::default());
+        assert!(candidates.iter().any(|(result, _)| result.quality == "major"));
+    }
+
+    #[test]
+    fn tolerance_accepts_detuned_intervals() {
+        let mut dictionary = ChordDictionary::empty();
+        dictionary.insert(vec![0, 4, 7], "major".to_string()).unwrap();
+        let peaks = chord_peaks(60, &[0, 3, 7]);
+
+        // 3度が1半音ずれているので、許容しない場合はコードにならない
+        let exact = analyze_chord_with_dictionary(peaks.clone(), 0, &dictionary).unwrap();
+        assert_eq!(exact.quality, "");
+
+        let fuzzy = analyze_chord_with_dictionary(peaks, 1, &dictionary).unwrap();
+        assert_eq!(fuzzy.quality, "major");
+        assert_eq!(fuzzy.deviations, 1);
+    }
+
+    #[test]
+    fn deviated_intervals_count_half() {
+        let observed = [(0, 1.0), (3, 1.0), (7, 1.0)];
+
+        let (matched, deviations, omitted, score) = match_intervals(&[0, 4, 7], &observed, 1);
+        assert_eq!((matched, deviations), (3, 1));
+        assert!(omitted.is_empty());
+        assert!((score - 2.5 / 3.0).abs() < 1e-12);
+
+        // 許容範囲を超えるずれは一致しない
+        let (matched, _, omitted, _) = match_intervals(&[0, 4, 7, 11], &[(0, 1.0), (4, 1.0), (7, 1.0), (9, 1.0)], 1);
+        assert_eq!(matched, 3);
+        assert_eq!(omitted, [11]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn chord_result_round_trips_through_json() {
+        let result = analyze_chord(chord_peaks(57, &[0, 3, 7])).unwrap();
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(json.contains(r#""root_note":"A""#));
+        assert!(json.contains(r#""quality":"minor""#));
+        assert_eq!(serde_json::from_str::<ChordResult>(&json).unwrap(), result);
+    }
+
+    #[test]
+    fn key_chooses_accidental_style() {
+        for key in ["Eb", "F", "Dm", "Bb", "Ab", "Gm", "Cm"] {
+            assert_eq!(AccidentalStyle::for_key(key), AccidentalStyle::Flat, "{}", key);
+        }
+        for key in ["C", "D", "A", "E", "F#", "Am", "Em", "C#m"] {
+            assert_eq!(AccidentalStyle::for_key(key), AccidentalStyle::Sharp, "{}", key);
+        }
+    }
+
+    #[test]
+    fn respell_converts_both_ways_and_keeps_octave() {
+        assert_eq!(AccidentalStyle::Flat.respell("C#4"), "Db4");
+        assert_eq!(AccidentalStyle::Sharp.respell("Db4"), "C#4");
+        assert_eq!(AccidentalStyle::Flat.respell("F#-1"), "Gb-1");
+        assert_eq!(AccidentalStyle::Sharp.respell("Gb7"), "F#7");
+        // 派生音でない音名や同じ表記の音名は変わらない
+        assert_eq!(AccidentalStyle::Flat.respell("E2"), "E2");
+        assert_eq!(AccidentalStyle::Sharp.respell("C#3"), "C#3");
+        assert_eq!(AccidentalStyle::Flat.respell("Db"), "Db");
+    }
+
+    #[test]
+    fn with_accidentals_respells_root_and_bass() {
+        // C#3 F3 G#3: C#メジャー、最低音がG#2の第2転回形
+        let result = analyze_chord(chord_peaks(44, &[0, 5, 9])).unwrap();
+        assert_eq!(result.to_string(), "C#3 major / G#2 bass");
+
+        let flat = result.clone().with_accidentals(AccidentalStyle::Flat);
+        assert_eq!(flat.to_string(), "Db3 major / Ab2 bass");
+        assert_eq!(flat.root_midi, result.root_midi);
+        assert_eq!(flat.with_accidentals(AccidentalStyle::Sharp), result);
+
+        assert_eq!(get_note(277.18, AccidentalStyle::Sharp, STANDARD_TUNING_HZ), "C#4");
+        assert_eq!(get_note(277.18, AccidentalStyle::Flat, STANDARD_TUNING_HZ), "Db4");
+    }
+
+    #[test]
+    fn get_note_follows_tuning() {
+        // A442の基準では442HzがA4、440Hzも8セントしかずれないのでA4
+        assert_eq!(get_note(442.0, AccidentalStyle::Sharp, 442.0), "A4");
+        assert_eq!(get_note(440.0, AccidentalStyle::Sharp, 442.0), "A4");
+        // A415の基準では440Hzが半音上のA#4になる
+        assert_eq!(get_note(440.0, AccidentalStyle::Sharp, 415.0), "A#4");
+        assert!((get_cents(442.0, STANDARD_TUNING_HZ) - 7.85).abs() < 0.01);
+        assert_eq!(get_cents(442.0, 442.0), 0.0);
+    }
+
+    #[test]
+    fn detect_notes_reports_cents_from_nearest_note() {
+        // A4より20セント高い音と、C4より30セント低い音
+        let peaks = [440.0 * 2f64.powf(20.0 / 1200.0), 261.6256 * 2f64.powf(-30.0 / 1200.0)];
+        let notes = detect_notes(&peaks, AccidentalStyle::Sharp, STANDARD_TUNING_HZ);
+
+        assert_eq!(notes[0].note, "A4");
+        assert!((notes[0].cents - 20.0).abs() < 1e-6);
+        assert_eq!(notes[0].to_string(), "A4 +20 cents");
+        assert_eq!(notes[1].note, "C4");
+        assert!((notes[1].cents + 30.0).abs() < 0.01);
+
+        // コードの結果にも各ピークの音名が入る
+        let result = analyze_chord(chord_peaks(60, &[0, 4, 7])).unwrap();
+        let names: Vec<&str> = result.notes.iter().map(|x| x.note.as_str()).collect();
+        assert_eq!(names, ["C4", "E4", "G4"]);
+    }
+
+    #[test]
+    fn reports_midi_note_numbers() {
+        assert_eq!(freq_to_midi(440.0, STANDARD_TUNING_HZ), 69);
+        assert_eq!(freq_to_midi(261.63, STANDARD_TUNING_HZ), 60);
+        assert_eq!(freq_to_midi(27.5, STANDARD_TUNING_HZ), 21);
+        // 基準音が変わっても基準音がA4 (69) になる
+        assert_eq!(freq_to_midi(415.0, 415.0), 69);
+
+        // G3 C4 E4: ルート音はC4 (60)、各ピークは55, 60, 64
+        let result = analyze_chord(chord_peaks(55, &[0, 5, 9])).unwrap();
+        assert_eq!(result.root_midi, 60);
+        let midi: Vec<i32> = result.notes.iter().map(|x| x.midi).collect();
+        assert_eq!(midi, [55, 60, 64]);
+    }
+
+    #[test]
+    fn inversion_reports_bass_and_position() {
+        // G3 C4 E4: Cメジャーの第2転回形
+        let result = analyze_chord(chord_peaks(55, &[0, 5, 9])).unwrap();
+
+        assert_eq!(result.quality, "major");
+        assert_eq!(result.root_note, "C");
+        assert_eq!(result.bass_note.as_deref(), Some("G3"));
+        assert_eq!(result.inversion, 2);
+        assert_eq!(result.to_string(), "C4 major / G3 bass");
+    }
+
+    #[test]
+    fn non_chord_bass_is_a_slash_chord() {
+        // F#2の上にC4 E4 G4: C/F#
+        let mut peaks = chord_peaks(60, &[0, 4, 7]);
+        peaks.push(midi_to_freq(42, STANDARD_TUNING_HZ));
+        let result = analyze_chord(peaks).unwrap();
+
+        assert_eq!(result.root_note, "C");
+        assert_eq!(result.quality, "major");
+        assert_eq!(result.bass_note.as_deref(), Some("F#2"));
+        assert_eq!(result.inversion, 0);
+    }
+
+    #[test]
+    fn clean_triad_is_more_confident_than_noisy_one() {
+        let clean = analyze_chord(chord_peaks(60, &[0, 4, 7])).unwrap();
+        assert_eq!(clean.confidence, 1.0);
+
+        // 構成音でない音が混ざると確からしさが下がる
+        let noisy = analyze_chord(chord_peaks(60, &[0, 4, 7, 18])).unwrap();
+        assert_eq!(noisy.quality, "major");
+        assert!(noisy.confidence < clean.confidence);
+    }
+
+    // Cから始まる音名とオクターブで、MIDIノート番号の音名を作る (get_noteとは別の計算で求める)
+    fn reference_name(midi: i32, style: AccidentalStyle) -> String {
+        let names = match style {
+            AccidentalStyle::Sharp => ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"],
+            AccidentalStyle::Flat => ["C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B"],
+        };
+        format!("{}{}", names[(midi % 12) as usize], midi / 12 - 1)
+    }
+
+    // MIDIノート番号の音からcentsだけずらした周波数
+    fn reference_freq(midi: i32, cents: f64, tuning_hz: f64) -> f64 {
+        tuning_hz * 2f64.powf((midi - 69) as f64 / 12.0 + cents / 1200.0)
+    }
+
+    #[test]
+    fn get_note_covers_c0_to_c8() {
+        for tuning_hz in [STANDARD_TUNING_HZ, 415.0, 432.0, 442.0] {
+            for style in [AccidentalStyle::Sharp, AccidentalStyle::Flat] {
+                // C0 (12) からC8 (108) まで
+                for midi in 12..=108 {
+                    for cents in [-40.0, 0.0, 40.0] {
+                        let freq = reference_freq(midi, cents, tuning_hz);
+                        let expected = reference_name(midi, style);
+                        assert_eq!(get_note(freq, style, tuning_hz), expected, "{}Hz (A4 = {}Hz)", freq, tuning_hz);
+                        assert_eq!(freq_to_midi(freq, tuning_hz), midi);
+                        assert!((get_cents(freq, tuning_hz) - cents).abs() < 1e-6);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn get_note_octave_boundaries() {
+        for (freq, note) in [(16.35, "C0"), (30.87, "B0"), (32.70, "C1"), (123.47, "B2"), (130.81, "C3"), (3951.07, "B7"), (4186.01, "C8")] {
+            assert_eq!(get_note(freq, AccidentalStyle::Sharp, STANDARD_TUNING_HZ), note, "{}Hz", freq);
+        }
+    }
+
+    #[test]
+    fn midi_to_freq_matches_reference_table() {
+        assert_eq!(midi_to_freq(69, STANDARD_TUNING_HZ), 440.0);
+        assert!((midi_to_freq(60, STANDARD_TUNING_HZ) - 261.6256).abs() < 1e-4);
+        assert!((midi_to_freq(69, 415.0) - 415.0).abs() < 1e-12);
+        assert!((midi_to_freq(81, 442.0) - 884.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn symmetric_chords_take_root_from_bass_in_every_rotation() {
+        // B3 D4 F4 G#4のディミニッシュセブンスと、C4 E4 G#4のオーギュメント
+        for (root_midi, intervals, quality) in [(59, &[0, 3, 6, 9][..], "diminished_seventh"), (60, &[0, 4, 8][..], "augmented")] {
+            for rotation in 0..intervals.len() {
+                // 下からrotation個の構成音を1オクターブ上げる
+                let voiced: Vec<i32> = intervals.iter().map(|&x| if x < intervals[rotation] { x + 12 } else { x }).collect();
+                let peaks = chord_peaks(root_midi, &voiced);
+                let bass_midi = root_midi + intervals[rotation];
+
+                let result = analyze_chord(peaks).unwrap();
+                assert_eq!(result.quality, quality, "{:?}", voiced);
+                assert_eq!(result.root_midi, bass_midi, "{:?}", voiced);
+                assert_eq!(result.bass_note, None);
+                assert_eq!(result.intervals, intervals);
+            }
+        }
+    }
+
+    #[test]
+    fn recognizes_extended_chords() {
+        for (intervals, quality) in [
+            (&[0, 4, 7, 10, 14][..], "ninth"),
+            (&[0, 4, 7, 11, 14][..], "major_ninth"),
+            (&[0, 3, 7, 10, 14][..], "minor_ninth"),
+            (&[0, 4, 7, 10, 14, 17][..], "eleventh"),
+            (&[0, 3, 7, 10, 14, 17][..], "minor_eleventh"),
+            (&[0, 4, 7, 10, 14, 21][..], "thirteenth"),
+        ] {
+            let result = analyze_chord(chord_peaks(48, intervals)).unwrap();
+            assert_eq!(result.quality, quality);
+            assert_eq!(result.root_note, "C");
+        }
+    }
+
+    #[test]
+    fn ninth_without_seventh_is_add9() {
+        // 7thがないので9thコードではなくadd9になる
+        let result = analyze_chord(chord_peaks(48, &[0, 4, 7, 14])).unwrap();
+        assert_eq!(result.quality, "add9");
+    }
+
+    #[test]
+    fn multi_octave_mode_distinguishes_flat_ninth_from_minor_second() {
+        let dictionary = ChordDictionary::default();
+        // C3 E3 G3 Bb3 Db4: 2オクターブにわたるC7(b9)
+        let wide = chord_peaks(48, &[0, 4, 7, 10, 13]);
+
+        let result = analyze_chord_with_mode(wide.clone(), 0, &dictionary, IntervalMode::MultiOctave).unwrap();
+        assert_eq!(result.quality, "seventh_flat_ninth");
+        assert_eq!(result.root_note, "C");
+        assert!(result.intervals.contains(&13));
+
+        // 1オクターブに畳み込むと区別できない
+        let folded = analyze_chord_with_mode(wide, 0, &dictionary, IntervalMode::SingleOctave).unwrap();
+        assert_ne!(folded.quality, "seventh_flat_ninth");
+
+        // 短2度のままではb9thとみなさない
+        let close = analyze_chord_with_mode(chord_peaks(48, &[0, 1, 4, 7, 10]), 0, &dictionary, IntervalMode::MultiOctave).unwrap();
+        assert_ne!(close.quality, "seventh_flat_ninth");
+    }
+
+    #[test]
+    fn multi_octave_mode_still_matches_folded_templates() {
+        // 12未満の音程は1オクターブ上の音にも一致する
+        let result = analyze_chord_with_mode(chord_peaks(48, &[0, 7, 16]), 0, &ChordDictionary::default(), IntervalMode::MultiOctave).unwrap();
+        assert_eq!(result.quality, "major");
+        assert_eq!(result.octave, 3);
+    }
+
+    #[test]
+    fn faint_extra_peak_barely_changes_weighted_match() {
+        let dictionary = ChordDictionary::default();
+        let mut peaks = chord_peaks(60, &[0, 4, 7]);
+        // 構成音でない小さなF#4
+        peaks.push(midi_to_freq(66, STANDARD_TUNING_HZ));
+
+        let weighted = analyze_chord_weighted(peaks.clone(), &[1.0, 1.0, 1.0, 0.01], 0, &dictionary, IntervalMode::SingleOctave).unwrap();
+        assert_eq!(weighted.to_string(), "C4 major");
+        assert!(weighted.confidence > 0.99, "{}", weighted.confidence);
+
+        // 重みが同じ場合は余分な音が一致度を大きく下げる
+        let unweighted = analyze_chord_with_dictionary(peaks, 0, &dictionary).unwrap();
+        assert!(unweighted.confidence < weighted.confidence);
+    }
+
+    #[test]
+    fn weights_must_match_peaks() {
+        let dictionary = ChordDictionary::default();
+        let peaks = chord_peaks(60, &[0, 4, 7]);
+
+        for weights in [&[1.0][..], &[1.0, 1.0, 1.0, 1.0][..]] {
+            let result = analyze_chord_weighted(peaks.clone(), weights, 0, &dictionary, IntervalMode::SingleOctave);
+            assert!(matches!(result, Err(AnalyzeError::InvalidInput(_))), "{:?}", weights);
+        }
+    }
+
+    #[test]
+    fn weak_chord_tone_counts_less() {
+        let observed = [(0, 1.0), (4, 1.0), (7, 0.25)];
+
+        let (_, _, _, score) = match_intervals(&[0, 4, 7], &observed, 0);
+        assert!((score - 2.25 / 3.0).abs() < 1e-12);
+    }
+
+    // 辞書の全てのコード (順番を固定するため音程で並べる)
+    fn templates() -> Vec<(Vec<i32>, String)> {
+        let mut templates: Vec<(Vec<i32>, String)> =
+            ChordDictionary::default().iter().map(|(intervals, name)| (intervals.clone(), name.clone())).collect();
+        templates.sort();
+        templates
+    }
+
+    // 1オクターブより上の音程を含むコードはMultiOctaveでだけ区別できる
+    fn mode_for(intervals: &[i32]) -> IntervalMode {
+        if intervals.iter().any(|&x| x >= 12) { IntervalMode::MultiOctave } else { IntervalMode::SingleOctave }
+    }
+
+    // 転回しても別のコードと同じ構成音にならないコード
+    const UNAMBIGUOUS: [&str; 6] = ["major", "minor", "diminished", "seventh", "major_seventh", "minor_major_seventh"];
+
+    proptest! {
+        #[test]
+        fn synthesized_chord_round_trips(
+            (intervals, quality) in prop::sample::select(templates()),
+            root in 36..84i32,
+            detune in prop::collection::vec(-20.0..20.0f64, 6),
+        ) {
+            // 各音を±20セントまでずらしても、最低音からの半音数は変わらない
+            let peaks: Vec<f64> = intervals.iter().zip(&detune)
+                .map(|(x, cents)| midi_to_freq(root + x, STANDARD_TUNING_HZ) * 2f64.powf(cents / 1200.0))
+                .collect();
+
+            let result = analyze_chord_with_mode(peaks, 0, &ChordDictionary::default(), mode_for(&intervals)).unwrap();
+            prop_assert_eq!(result.quality, quality);
+            prop_assert_eq!(result.root_midi.rem_euclid(12), root.rem_euclid(12));
+        }
+
+        #[test]
+        fn peak_order_does_not_matter(
+            (intervals, _) in prop::sample::select(templates()),
+            root in 36..84i32,
+            keys in prop::collection::vec(any::<u32>(), 6),
+        ) {
+            let peaks = chord_peaks(root, &intervals);
+            // ランダムな鍵で並べ替えてピークの順番を入れ替える
+            let mut keyed: Vec<(u32, f64)> = keys.into_iter().zip(peaks.iter().copied()).collect();
+            keyed.sort_by_key(|x| x.0);
+            let reordered: Vec<f64> = keyed.into_iter().map(|x| x.1).collect();
+
+            let mode = mode_for(&intervals);
+            let expected = analyze_chord_with_mode(peaks, 0, &ChordDictionary::default(), mode).unwrap();
+            let result = analyze_chord_with_mode(reordered, 0, &ChordDictionary::default(), mode).unwrap();
+            prop_assert_eq!(result.to_string(), expected.to_string());
+            prop_assert_eq!(result.intervals, expected.intervals);
+        }
+
+        #[test]
+        fn inversions_keep_root_and_quality(
+            quality in prop::sample::select(UNAMBIGUOUS.to_vec()),
+            root in 36..72i32,
+            inversion in 0..4usize,
+        ) {
+            let dictionary = ChordDictionary::default();
+            let (intervals, _) = dictionary.iter().find(|(_, name)| *name == quality).unwrap();
+            let inversion = inversion % intervals.len();
+
+            // 下からinversion個の構成音を1オクターブ上げる
+            let voiced: Vec<i32> = intervals.iter().enumerate().map(|(i, x)| if i < inversion { x + 12 } else { *x }).collect();
+            let result = analyze_chord(chord_peaks(root, &voiced)).unwrap();
+
+            prop_assert_eq!(&result.quality, quality);
+            prop_assert_eq!(result.root_midi.rem_euclid(12), root.rem_euclid(12));
+            prop_assert_eq!(result.inversion, inversion);
+            prop_assert_eq!(result.bass_note.is_some(), inversion > 0);
+        }
+
+        #[test]
+        fn octave_transposition_keeps_chord(
+            (intervals, _) in prop::sample::select(templates()),
+            root in 36..72i32,
+            octaves in -2..=2i32,
+        ) {
+            let mode = mode_for(&intervals);
+            let peaks = chord_peaks(root, &intervals);
+            let transposed: Vec<f64> = peaks.iter().map(|x| x * 2f64.powi(octaves)).collect();
+
+            let expected = analyze_chord_with_mode(peaks, 0, &ChordDictionary::default(), mode).unwrap();
+            let result = analyze_chord_with_mode(transposed, 0, &ChordDictionary::default(), mode).unwrap();
+            prop_assert_eq!(&result.quality, &expected.quality);
+            prop_assert_eq!(&result.root_note, &expected.root_note);
+            prop_assert_eq!(result.octave, expected.octave + octaves);
+            prop_assert_eq!(result.inversion, expected.inversion);
+            prop_assert_eq!(result.intervals, expected.intervals);
+        }
+
+        #[test]
+        fn get_note_round_trips_midi_to_freq(
+            midi in 12..=108i32,
+            cents in -49.0..49.0f64,
+            tuning_hz in 400.0..480.0f64,
+            flat in any::<bool>(),
+        ) {
+            let style = if flat { AccidentalStyle::Flat } else { AccidentalStyle::Sharp };
+            let freq = midi_to_freq(midi, tuning_hz) * 2f64.powf(cents / 1200.0);
+
+            prop_assert_eq!(freq_to_midi(freq, tuning_hz), midi);
+            prop_assert_eq!(get_note(freq, style, tuning_hz), reference_name(midi, style));
+            prop_assert!((get_cents(freq, tuning_hz) - cents).abs() < 1e-6);
+        }
+    }
+}
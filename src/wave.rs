@@ -0,0 +1,287 @@
+use crate::{AnalyzeError, AnalyzeOptions, AnalyzeWarning, SampleRateCheck, WindowKind, apply_window, downmix, required_sample_rate};
+#[cfg(feature = "fft")]
+use crate::{AnalysisDetail, ChordResult, analyze_detailed, analyze_samples, resample, select_range};
+use hound::WavSpec;
+
+// 整数のサンプルを読み込み、-1.0〜1.0の範囲に正規化する
+fn read_samples<S, R>(reader: &mut hound::WavReader<R>, bits: u16) -> Result<Vec<f64>, hound::Error>
+where
+    S: hound::Sample + Into<i32>,
+    R: std::io::Read,
+{
+    // 32bitの場合にi32の範囲を超えないようにf64で計算する
+    let scale = 2f64.powi(bits as i32 - 1);
+    reader
+        .samples::<S>()
+        .map(|s| s.map(|s| s.into() as f64 / scale))
+        .collect()
+}
+
+// Wavファイルを読み込み、窓関数を適用したデータを返す
+pub fn get_wave(path: &str, window: WindowKind) -> Result<(WavSpec, Vec<f64>), AnalyzeError> {
+    let mut target = hound::WavReader::open(path).map_err(|e| AnalyzeError::from_hound(e, path))?;
+
+    let spec = target.spec();
+
+    let samples = match (spec.sample_format, spec.bits_per_sample) {
+        // 8bitはhound側で符号付きに変換されている
+        (hound::SampleFormat::Int, 8) => read_samples::<i8, _>(&mut target, 8),
+        (hound::SampleFormat::Int, 16) => read_samples::<i16, _>(&mut target, 16),
+        (hound::SampleFormat::Int, 24) | (hound::SampleFormat::Int, 32) => {
+            read_samples::<i32, _>(&mut target, spec.bits_per_sample)
+        },
+        (hound::SampleFormat::Float, 32) => {
+            // 32bit floatの場合
+            target
+                .samples::<f32>()
+                .map(|s| s.map(|s| s as f64))
+                .collect::<Result<Vec<f64>, _>>()
+        },
+        (format, bits) => {
+            return Err(AnalyzeError::UnsupportedFormat(format!("{:?} {}bit", format, bits)));
+        },
+    }.map_err(|e| AnalyzeError::from_hound(e, path))?;
+
+    if samples.is_empty() {
+        return Err(AnalyzeError::EmptySignal);
+    }
+
+    // 複数チャンネルの場合はモノラルにまとめる
+    let samples = downmix(&samples, spec.channels);
+
+    // 窓関数を適用
+    Ok((spec, apply_window(&samples, window)))
+}
+
+// 読み込める音声ファイルの拡張子かどうかを返す
+//
+// Wav以外はfeatureで有効にしたフォーマットだけに対応する。
+pub fn is_supported_extension(ext: &str) -> bool {
+    let ext = ext.to_ascii_lowercase();
+    ext == "wav"
+        || (cfg!(feature = "flac") && ext == "flac")
+        || (cfg!(feature = "ogg") && matches!(ext.as_str(), "ogg" | "oga"))
+        || (cfg!(feature = "mp3") && ext == "mp3")
+}
+
+// 拡張子に応じて音声ファイルを読み込み、窓関数を適用したデータを返す
+pub fn get_audio(path: &str, window: WindowKind) -> Result<(WavSpec, Vec<f64>), AnalyzeError> {
+    let ext = std::path::Path::new(path)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if ext == "wav" || ext.is_empty() {
+        return get_wave(path, window);
+    }
+    if !is_supported_extension(&ext) {
+        return Err(AnalyzeError::UnsupportedFormat(ext));
+    }
+
+    #[cfg(feature = "decode")]
+    {
+        let (spec, samples) = crate::decode::decode_file(path)?;
+        if samples.is_empty() {
+            return Err(AnalyzeError::EmptySignal);
+        }
+
+        let samples = downmix(&samples, spec.channels);
+        Ok((spec, apply_window(&samples, window)))
+    }
+
+    // 対応する拡張子はfeatureが無効なら存在しない
+    #[cfg(not(feature = "decode"))]
+    unreachable!()
+}
+
+// 音声ファイルを解析してコードを返す
+#[cfg(feature = "fft")]
+pub fn analyze_file(path: &str) -> Result<ChordResult, AnalyzeError> {
+    analyze_file_with(path, &AnalyzeOptions::default())
+}
+
+// 設定を指定して音声ファイルを解析し、コードを返す
+//
+// 警告は返さないため、必要な場合はanalyze_file_detailedを使う。
+#[cfg(feature = "fft")]
+pub fn analyze_file_with(path: &str, options: &AnalyzeOptions) -> Result<ChordResult, AnalyzeError> {
+    let (samples, sample_rate, _) = read_for_analysis(path, options)?;
+
+    analyze_samples(&samples, sample_rate, options)
+}
+
+// 設定を指定して音声ファイルを解析し、途中結果を含めた解析結果を返す
+#[cfg(feature = "fft")]
+pub fn analyze_file_detailed(path: &str, options: &AnalyzeOptions) -> Result<AnalysisDetail, AnalyzeError> {
+    let (samples, sample_rate, warnings) = read_for_analysis(path, options)?;
+
+    let mut detail = analyze_detailed(&samples, sample_rate, options)?;
+    detail.warnings = warnings;
+    Ok(detail)
+}
+
+// 音声ファイルの形式が解析に使えるか確認し、解析は続けられる問題を警告として返す
+//
+// サンプリング周波数が低すぎる場合はsample_rate_checkに従って警告するかエラーを返す。
+pub fn validate_spec(spec: &WavSpec, options: &AnalyzeOptions) -> Result<Vec<AnalyzeWarning>, AnalyzeError> {
+    if spec.channels == 0 || spec.sample_rate == 0 {
+        return Err(AnalyzeError::UnsupportedFormat(format!("{}ch {}Hz", spec.channels, spec.sample_rate)));
+    }
+
+    let mut warnings = Vec::new();
+    let required = required_sample_rate(options);
+    if spec.sample_rate < required {
+        match options.sample_rate_check {
+            SampleRateCheck::Ignore => {},
+            SampleRateCheck::Warn => warnings.push(AnalyzeWarning::LowSampleRate { sample_rate: spec.sample_rate, recommended: required }),
+            SampleRateCheck::Error => return Err(AnalyzeError::SampleRateTooLow(spec.sample_rate)),
+        }
+    }
+
+    Ok(warnings)
+}
+
+// 音声ファイルを読み込み、設定に応じて変換して窓関数を適用した信号とサンプリング周波数、警告を返す
+#[cfg(feature = "fft")]
+fn read_for_analysis(path: &str, options: &AnalyzeOptions) -> Result<(Vec<f64>, u32, Vec<AnalyzeWarning>), AnalyzeError> {
+    let (spec, samples) = get_audio(path, WindowKind::Rectangular)?;
+    let warnings = validate_spec(&spec, options)?;
+
+    // 窓関数は範囲を切り出し、サンプリング周波数を変換してから適用する
+    let samples = select_range(&samples, spec.sample_rate, options.start_sec, options.duration_sec)?;
+    let (samples, rate) = match options.resample_hz {
+        Some(rate) => (resample(samples, spec.sample_rate, rate)?, rate),
+        None => (samples.to_vec(), spec.sample_rate),
+    };
+
+    Ok((apply_window(&samples, options.window), rate, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{TempFile, sine};
+
+    // サンプルを指定した形式のWavファイルに書き出し、そのパスを返す
+    fn write_test_wave<S: hound::Sample + Copy>(name: &str, spec: WavSpec, samples: &[S]) -> TempFile {
+        let file = TempFile::new(name);
+        let mut writer = hound::WavWriter::create(file.path(), spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+        file
+    }
+
+    fn int_spec(channels: u16, bits_per_sample: u16) -> WavSpec {
+        WavSpec { channels, sample_rate: 44100, bits_per_sample, sample_format: hound::SampleFormat::Int }
+    }
+
+    #[test]
+    fn reads_each_integer_bit_depth_scaled_to_unit_range() {
+        let expected = [0.5, -0.5, 0.25, 0.0];
+
+        let file = write_test_wave("8bit.wav", int_spec(1, 8), &[64i8, -64, 32, 0]);
+        assert_eq!(get_wave(file.path(), WindowKind::Rectangular).unwrap().1, expected);
+
+        let file = write_test_wave("16bit.wav", int_spec(1, 16), &[16384i16, -16384, 8192, 0]);
+        assert_eq!(get_wave(file.path(), WindowKind::Rectangular).unwrap().1, expected);
+
+        let file = write_test_wave("24bit.wav", int_spec(1, 24), &[1i32 << 22, -(1 << 22), 1 << 21, 0]);
+        assert_eq!(get_wave(file.path(), WindowKind::Rectangular).unwrap().1, expected);
+
+        let file = write_test_wave("32bit.wav", int_spec(1, 32), &[1i32 << 30, -(1 << 30), 1 << 29, 0]);
+        assert_eq!(get_wave(file.path(), WindowKind::Rectangular).unwrap().1, expected);
+    }
+
+    #[test]
+    fn reads_full_scale_32bit_samples_without_overflow() {
+        let file = write_test_wave("32bit_full.wav", int_spec(1, 32), &[i32::MIN, i32::MAX]);
+        let (_, samples) = get_wave(file.path(), WindowKind::Rectangular).unwrap();
+
+        assert_eq!(samples[0], -1.0);
+        assert!((samples[1] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reads_32bit_float_samples() {
+        let spec = WavSpec { channels: 1, sample_rate: 44100, bits_per_sample: 32, sample_format: hound::SampleFormat::Float };
+        let file = write_test_wave("float.wav", spec, &[0.5f32, -0.25]);
+
+        assert_eq!(get_wave(file.path(), WindowKind::Rectangular).unwrap().1, [0.5, -0.25]);
+    }
+
+    #[test]
+    fn downmixes_stereo_file_to_average_of_channels() {
+        // 左右に異なる音を入れる
+        let left = sine(&[440.0], 44100, 1000);
+        let right = sine(&[660.0], 44100, 1000);
+        let interleaved: Vec<i16> = left.iter().zip(&right)
+            .flat_map(|(l, r)| [(l * 16384.0) as i16, (r * 16384.0) as i16])
+            .collect();
+        let file = write_test_wave("stereo.wav", int_spec(2, 16), &interleaved);
+
+        let (spec, samples) = get_wave(file.path(), WindowKind::Rectangular).unwrap();
+
+        assert_eq!(spec.channels, 2);
+        assert_eq!(samples.len(), 1000);
+        for (i, x) in samples.iter().enumerate() {
+            let expected = (interleaved[2 * i] as f64 + interleaved[2 * i + 1] as f64) / 2.0 / 32768.0;
+            assert!((x - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn empty_file_is_an_error() {
+        let file = write_test_wave::<i16>("empty.wav", int_spec(1, 16), &[]);
+
+        assert!(matches!(get_wave(file.path(), WindowKind::Hamming), Err(AnalyzeError::EmptySignal)));
+    }
+
+    #[test]
+    fn missing_file_is_reported() {
+        let file = TempFile::new("missing.wav");
+
+        assert!(matches!(get_audio(file.path(), WindowKind::Hamming), Err(AnalyzeError::FileNotFound(_))));
+    }
+
+    #[cfg(not(feature = "mp3"))]
+    #[test]
+    fn disabled_format_is_unsupported() {
+        let file = TempFile::new("disabled.mp3");
+        std::fs::write(file.path(), [0u8; 16]).unwrap();
+
+        assert!(!is_supported_extension("mp3"));
+        assert!(matches!(get_audio(file.path(), WindowKind::Hamming), Err(AnalyzeError::UnsupportedFormat(_))));
+    }
+
+    #[cfg(feature = "fft")]
+    #[test]
+    fn low_sample_rate_follows_check_setting() {
+        let samples: Vec<i16> = sine(&[440.0], 8000, 4096).iter().map(|x| (x * 16384.0) as i16).collect();
+        let spec = WavSpec { sample_rate: 8000, ..int_spec(1, 16) };
+        let file = write_test_wave("low_rate.wav", spec, &samples);
+        let with_check = |sample_rate_check| AnalyzeOptions { sample_rate_check, ..AnalyzeOptions::default() };
+
+        assert!(matches!(
+            analyze_file_with(file.path(), &with_check(SampleRateCheck::Error)),
+            Err(AnalyzeError::SampleRateTooLow(8000))
+        ));
+
+        let detail = analyze_file_detailed(file.path(), &with_check(SampleRateCheck::Warn)).unwrap();
+        assert_eq!(detail.warnings, [AnalyzeWarning::LowSampleRate { sample_rate: 8000, recommended: 8372 }]);
+        assert_eq!(detail.chord.notes[0].note, "A4");
+
+        let detail = analyze_file_detailed(file.path(), &with_check(SampleRateCheck::Ignore)).unwrap();
+        assert!(detail.warnings.is_empty());
+    }
+
+    #[test]
+    fn sufficient_sample_rate_has_no_warnings() {
+        assert!(validate_spec(&int_spec(2, 16), &AnalyzeOptions::default()).unwrap().is_empty());
+        assert!(matches!(
+            validate_spec(&int_spec(0, 16), &AnalyzeOptions::default()),
+            Err(AnalyzeError::UnsupportedFormat(_))
+        ));
+    }
+}